@@ -0,0 +1,53 @@
+//! 현재 시각. 날짜에 따라 달라지는 기능(오늘의 퀴즈 등)은 `Clock`을 주입받아서 쓴다
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 날짜 기준은 한국 시간
+const KST_OFFSET_SECS: i64 = 9 * 60 * 60;
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+
+    /// 1970-01-01(KST)부터 며칠째인지
+    fn today(&self) -> i64 {
+        kst_days(self.now())
+    }
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+pub fn kst_days(time: SystemTime) -> i64 {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    (secs + KST_OFFSET_SECS).div_euclid(SECS_PER_DAY)
+}
+
+/// 그날(KST)이 끝나는 시각 (다음 날 0시)
+pub fn end_of_day(days: i64) -> SystemTime {
+    UNIX_EPOCH + std::time::Duration::from_secs(((days + 1) * SECS_PER_DAY - KST_OFFSET_SECS).max(0) as u64)
+}
+
+/// 19000 -> "2022-01-08"
+pub fn format_days(days: i64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
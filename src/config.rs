@@ -1,6 +1,15 @@
 use crate::{Error, Result};
-use std::env;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::OnceLock;
+use std::time::Duration;
+
+const DEFAULT_PAUSED_GAME_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const DEFAULT_IDLE_GAME_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+const DEFAULT_KAKAO_EVENT_API_URL: &str = "https://bot-api.kakao.com";
+
+// `STATIC_FILES_DIR`을 서빙하는 경로
+pub const STATIC_ROUTE_PATH: &str = "/static";
 
 pub fn config() -> &'static Config {
     static INSTANCE: OnceLock<Config> = OnceLock::new();
@@ -12,23 +21,194 @@ pub fn config() -> &'static Config {
     })
 }
 
+// 환경 변수 이름 -> 값
+type EnvLookup<'a> = &'a dyn Fn(&'static str) -> Option<String>;
+
 #[allow(non_snake_case)]
 pub struct Config {
     pub PRESHARED_AUTH_HEADER_KEY: String,
     pub PRESHARED_AUTH_HEADER_VALUE: String,
     pub REDIS_CONNECTION_STRING: String,
+
+    // 이미지 퀴즈 세트별 base url. ex) "로고=https://cdn.example.com/logos,랜드마크=https://..."
+    pub IMAGE_QUIZ_BASE_URLS: HashMap<String, String>,
+
+    // 설정하면 `/static/...` 으로 로컬 파일을 서빙한다. ex) "/home/deploy/static"
+    pub STATIC_FILES_DIR: Option<String>,
+    // 설정하면 이미지 url을 CDN 대신 `{PUBLIC_BASE_URL}/static/...` 으로 만든다
+    pub PUBLIC_BASE_URL: Option<String>,
+
+    // 일시정지한 게임을 이 시간이 지나면 자동 종료. 기본 10분
+    pub PAUSED_GAME_TIMEOUT: Duration,
+    // 아무 활동이 없는 게임을 이 시간이 지나면 자동 종료. 기본 30분
+    pub IDLE_GAME_TIMEOUT: Duration,
+
+    // 진행 중인 게임을 어디에 저장할지. "redis"(기본), "file:/path/to/dir", "memory"
+    pub GAME_STORE: GameStoreConfig,
+    // 인스턴스 여러 개를 띄울 때. 게임 상태를 매번 GAME_STORE에서 읽고 방마다 잠근다
+    pub DISTRIBUTED: bool,
+
+    // Event API (봇이 먼저 보내는 메시지). 둘 다 있어야 실제로 보낸다
+    pub KAKAO_REST_API_KEY: Option<String>,
+    pub KAKAO_BOT_ID: Option<String>,
+    pub KAKAO_EVENT_API_URL: String,
+}
+
+pub enum GameStoreConfig {
+    Redis,
+    File(PathBuf),
+    // 재시작하면 사라진다
+    Memory,
+}
+
+impl GameStoreConfig {
+    fn parse(name: &'static str, value: Option<String>) -> Result<Self> {
+        let Some(value) = value else {
+            return Ok(GameStoreConfig::Redis);
+        };
+
+        match value.trim() {
+            "redis" => Ok(GameStoreConfig::Redis),
+            "memory" => Ok(GameStoreConfig::Memory),
+            value => value.strip_prefix("file:")
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| GameStoreConfig::File(PathBuf::from(dir)))
+                .ok_or(Error::ConfigWrongFormat(name)),
+        }
+    }
 }
 
 impl Config {
+    // 테스트는 환경 변수 대신 고정된 값으로
+    #[cfg(not(test))]
+    fn load_from_env() -> Result<Config> {
+        Self::load(&|name| std::env::var(name).ok())
+    }
+
+    #[cfg(test)]
     fn load_from_env() -> Result<Config> {
-        Ok(Config {
-            PRESHARED_AUTH_HEADER_KEY: get_env("PRESHARED_AUTH_HEADER_KEY")?,
-            PRESHARED_AUTH_HEADER_VALUE: get_env("PRESHARED_AUTH_HEADER_VALUE")?,
-            REDIS_CONNECTION_STRING: get_env("REDIS_CONNECTION_STRING")?,
+        Self::load(&|name| match name {
+            "PRESHARED_AUTH_HEADER_KEY" => Some("X-Test-Auth".to_string()),
+            "PRESHARED_AUTH_HEADER_VALUE" => Some("test".to_string()),
+            "REDIS_CONNECTION_STRING" => Some("redis://127.0.0.1:1".to_string()),
+            "GAME_STORE" => Some("memory".to_string()),
+            _ => None,
         })
     }
+
+    fn load(env: EnvLookup) -> Result<Config> {
+        let config = Config {
+            PRESHARED_AUTH_HEADER_KEY: get_env(env, "PRESHARED_AUTH_HEADER_KEY")?,
+            PRESHARED_AUTH_HEADER_VALUE: get_env(env, "PRESHARED_AUTH_HEADER_VALUE")?,
+            REDIS_CONNECTION_STRING: get_env(env, "REDIS_CONNECTION_STRING")?,
+            IMAGE_QUIZ_BASE_URLS: parse_key_values(
+                "IMAGE_QUIZ_BASE_URLS",
+                &env("IMAGE_QUIZ_BASE_URLS").unwrap_or_default(),
+            )?,
+            STATIC_FILES_DIR: env("STATIC_FILES_DIR"),
+            PUBLIC_BASE_URL: env("PUBLIC_BASE_URL")
+                .map(|url| url.trim_end_matches('/').to_string()),
+            PAUSED_GAME_TIMEOUT: get_env_secs(env, "PAUSED_GAME_TIMEOUT_SECS", DEFAULT_PAUSED_GAME_TIMEOUT)?,
+            IDLE_GAME_TIMEOUT: get_env_secs(env, "IDLE_GAME_TIMEOUT_SECS", DEFAULT_IDLE_GAME_TIMEOUT)?,
+            GAME_STORE: GameStoreConfig::parse("GAME_STORE", env("GAME_STORE"))?,
+            DISTRIBUTED: get_env_bool(env, "DISTRIBUTED")?,
+            KAKAO_REST_API_KEY: env("KAKAO_REST_API_KEY"),
+            KAKAO_BOT_ID: env("KAKAO_BOT_ID"),
+            KAKAO_EVENT_API_URL: env("KAKAO_EVENT_API_URL")
+                .unwrap_or_else(|| DEFAULT_KAKAO_EVENT_API_URL.to_string()),
+        };
+
+        // 직접 서빙하지 않는데 이미지 url을 `/static`으로 만들면 깨진다
+        if config.PUBLIC_BASE_URL.is_some() && config.STATIC_FILES_DIR.is_none() {
+            return Err(Error::ConfigWrongFormat("PUBLIC_BASE_URL"));
+        }
+        // 메모리 저장소는 다른 프로세스와 나눠 쓸 수 없다
+        if config.DISTRIBUTED && matches!(config.GAME_STORE, GameStoreConfig::Memory) {
+            return Err(Error::ConfigWrongFormat("GAME_STORE"));
+        }
+        // Event API는 둘 다 있어야 보낼 수 있다. 하나만 있으면 빠뜨린 것
+        match (&config.KAKAO_REST_API_KEY, &config.KAKAO_BOT_ID) {
+            (Some(_), None) => return Err(Error::ConfigWrongFormat("KAKAO_BOT_ID")),
+            (None, Some(_)) => return Err(Error::ConfigWrongFormat("KAKAO_REST_API_KEY")),
+            _ => {}
+        }
+        Ok(config)
+    }
+}
+
+impl Config {
+    /// `PUBLIC_BASE_URL`이 설정되어 있으면 `{PUBLIC_BASE_URL}/static/{path}`
+    pub fn public_static_url(&self, path: &str) -> Option<String> {
+        let base_url = self.PUBLIC_BASE_URL.as_ref()?;
+        Some(format!("{}{}/{}", base_url, STATIC_ROUTE_PATH, path.trim_start_matches('/')))
+    }
+}
+
+fn get_env(env: EnvLookup, name: &'static str) -> Result<String> {
+    env(name).ok_or(Error::ConfigMissingEnv(name))
+}
+
+/// 초 단위 정수. 없으면 기본값
+fn get_env_secs(env: EnvLookup, name: &'static str, default: Duration) -> Result<Duration> {
+    match env(name) {
+        Some(value) => value.trim().parse::<u64>()
+            .map(Duration::from_secs)
+            .map_err(|_| Error::ConfigWrongFormat(name)),
+        None => Ok(default),
+    }
+}
+
+/// "true"/"1" 또는 "false"/"0". 없으면 false
+fn get_env_bool(env: EnvLookup, name: &'static str) -> Result<bool> {
+    match env(name).as_deref().map(str::trim) {
+        Some("true" | "1") => Ok(true),
+        Some("false" | "0") | None => Ok(false),
+        Some(_) => Err(Error::ConfigWrongFormat(name)),
+    }
 }
 
-fn get_env(name: &'static str) -> Result<String> {
-    env::var(name).map_err(|_| Error::ConfigMissingEnv(name))
+/// "a=1,b=2" -> {a: 1, b: 2}
+fn parse_key_values(name: &'static str, value: &str) -> Result<HashMap<String, String>> {
+    value.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').ok_or(Error::ConfigWrongFormat(name))?;
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_with(extra: &'static [(&'static str, &'static str)]) -> Result<Config> {
+        Config::load(&|name| match name {
+            "PRESHARED_AUTH_HEADER_KEY" => Some("X-Test-Auth".to_string()),
+            "PRESHARED_AUTH_HEADER_VALUE" => Some("test".to_string()),
+            "REDIS_CONNECTION_STRING" => Some("redis://127.0.0.1:1".to_string()),
+            name => extra.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string()),
+        })
+    }
+
+    #[test]
+    fn event_api_needs_both_key_and_bot_id() {
+        let config = load_with(&[]).unwrap();
+        assert!(config.KAKAO_REST_API_KEY.is_none() && config.KAKAO_BOT_ID.is_none());
+
+        let config = load_with(&[("KAKAO_REST_API_KEY", "key"), ("KAKAO_BOT_ID", "bot")]).unwrap();
+        assert_eq!(config.KAKAO_BOT_ID.as_deref(), Some("bot"));
+
+        assert!(matches!(
+            load_with(&[("KAKAO_REST_API_KEY", "key")]),
+            Err(Error::ConfigWrongFormat("KAKAO_BOT_ID"))
+        ));
+        assert!(matches!(
+            load_with(&[("KAKAO_BOT_ID", "bot")]),
+            Err(Error::ConfigWrongFormat("KAKAO_REST_API_KEY"))
+        ));
+    }
 }
@@ -4,6 +4,7 @@ use kakao_rs::prelude::{SimpleText, Template};
 use serde::Serialize;
 use serde_with::serde_as;
 use crate::game::state::GroupKey;
+use crate::game::team::Team;
 use crate::web::model::ChatIdType;
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -14,8 +15,12 @@ pub type Result<T> = core::result::Result<T, Error>;
 pub enum Error {
     // -- Sys
     ConfigMissingEnv(&'static str),
+    ConfigWrongFormat(&'static str),
     RedisConnectionGetFail,
     RedisCommandFail(String), // key
+    GameStoreFail(String),
+    EventSendFail(String),
+    CallbackSendFail(String),
 
     // -- Bot
     AuthFail,
@@ -27,6 +32,29 @@ pub enum Error {
     GameAlreadyStarted(GroupKey),
     GameAlreadyFinished(GroupKey),  // unused
     GameInvalidCategoryName,
+    GameDifficultyNotSupported(String),  // category
+    GameNotTeamBattle(GroupKey),
+    // 이미 답해서 팀을 바꿀 수 없다. 지금 팀
+    GameTeamLocked(Team),
+    GamePaused(GroupKey),
+    GameNoHint(GroupKey),
+    // 다른 인스턴스가 같은 방을 처리하는 중
+    GameBusy(GroupKey),
+    GameNotPaused(GroupKey),
+    ChallengeNotFound(String),  // code
+    ChallengeOwnRoom(String),
+    ChallengeAlreadyAccepted(String),
+    LobbyAlreadyOpen(GroupKey),
+    LobbyNotFound(GroupKey),
+    LobbyNotHost,
+    // 인스턴스마다 따로 들고 있는 기능 (대기실, 도전). 여러 인스턴스일 때는 막는다
+    GameNotDistributable(&'static str),  // 기능 이름
+    DailyAlreadyAttempted,
+    DailyNotStarted,
+    // 같은 문제에 먼저 처리된 답이 있다
+    DailyAlreadyAnswered,
+    NicknameInvalid(&'static str),  // 이유
+    NicknameTaken(String),
 }
 
 impl std::fmt::Display for Error {
@@ -37,6 +65,84 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+impl Error {
+    /// 사용자에게 보여줄 응답. 콜백으로 나중에 보낼 때도 쓴다
+    pub fn template(&self) -> Template {
+        let mut template = Template::new();
+        
+        match self {
+            Error::GameCommandParseFail(help_message) => {
+                template.add_output(SimpleText::new(*help_message).build());
+            }
+            Error::GameNotFound(_group_key) => {
+                template.add_output(SimpleText::new("게임중이 아니에요").build());
+            }
+            Error::GameNotTeamBattle(_group_key) => {
+                template.add_output(SimpleText::new("팀전이 아니에요. '시작 팀전'으로 시작해 주세요").build());
+            }
+            Error::GameDifficultyNotSupported(category) => {
+                template.add_output(SimpleText::new(format!("'{}' 카테고리는 난이도를 고를 수 없어요. 상식, 고사성어, 넌센스, 수학에서 골라 주세요", category)).build());
+            }
+            Error::GameTeamLocked(team) => {
+                template.add_output(SimpleText::new(format!("이미 {}으로 답해서 팀을 바꿀 수 없어요", team.name())).build());
+            }
+            Error::GamePaused(_group_key) => {
+                template.add_output(SimpleText::new("⏸️ 일시정지 중이에요. '재개'로 다시 시작하세요").build());
+            }
+            Error::GameNoHint(_group_key) => {
+                template.add_output(SimpleText::new("이 문제는 힌트가 없어요").build());
+            }
+            Error::GameNotPaused(_group_key) => {
+                template.add_output(SimpleText::new("일시정지 중이 아니에요").build());
+            }
+            Error::GameBusy(_group_key) => {
+                template.add_output(SimpleText::new("요청이 몰리고 있어요. 잠시 후 다시 시도해 주세요").build());
+            }
+            Error::ChallengeNotFound(code) => {
+                template.add_output(SimpleText::new(format!("도전 {}을(를) 찾을 수 없어요. 코드가 틀렸거나 시간이 지났어요", code)).build());
+            }
+            Error::ChallengeOwnRoom(_code) => {
+                template.add_output(SimpleText::new("우리 방이 만든 도전장이에요. 다른 방에서 입력해 주세요").build());
+            }
+            Error::ChallengeAlreadyAccepted(code) => {
+                template.add_output(SimpleText::new(format!("도전 {}은(는) 이미 다른 방이 받았어요", code)).build());
+            }
+            Error::LobbyAlreadyOpen(_group_key) => {
+                template.add_output(SimpleText::new("대기실이 열려 있어요. '참가'로 참여하거나 방장이 '출발'하면 시작합니다").build());
+            }
+            Error::LobbyNotFound(_group_key) => {
+                template.add_output(SimpleText::new("열린 대기실이 없어요. '시작 [카테고리] 대기실'로 열 수 있어요").build());
+            }
+            Error::LobbyNotHost => {
+                template.add_output(SimpleText::new("대기실을 연 사람만 출발할 수 있어요").build());
+            }
+            Error::GameNotDistributable(feature) => {
+                template.add_output(SimpleText::new(format!("지금은 {}을(를) 쓸 수 없어요. '시작'으로 바로 게임을 시작해 주세요", feature)).build());
+            }
+            Error::DailyAlreadyAttempted => {
+                template.add_output(SimpleText::new("오늘의 퀴즈는 하루 한 번만 풀 수 있어요. '오늘의 퀴즈 결과'로 순위를 확인해 보세요").build());
+            }
+            Error::DailyNotStarted => {
+                template.add_output(SimpleText::new("'오늘의 퀴즈'로 먼저 시작해 주세요").build());
+            }
+            Error::DailyAlreadyAnswered => {
+                template.add_output(SimpleText::new("이 문제는 이미 답했어요").build());
+            }
+            Error::NicknameInvalid(reason) => {
+                template.add_output(SimpleText::new(*reason).build());
+            }
+            Error::NicknameTaken(nickname) => {
+                template.add_output(SimpleText::new(format!("'{}'은(는) 다른 사람이 쓰고 있어요", nickname)).build());
+            }
+            _ => {
+                template.add_output(SimpleText::new(format!("err: {self:?}").as_str()).build());
+            }
+        }
+
+        template
+    }
+}
+
 // TODO: debug mode일 때만 에러 변환
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
@@ -49,19 +155,7 @@ impl IntoResponse for Error {
         // response.extensions_mut().insert(self);
         
         // Json<Template>
-        let mut template = Template::new();
-        
-        match self {
-            Error::GameCommandParseFail(help_message) => {
-                template.add_output(SimpleText::new(help_message).build());
-            }
-            Error::GameNotFound(group_key) => {
-                template.add_output(SimpleText::new("게임중이 아니에요").build());
-            }
-            _ => {
-                template.add_output(SimpleText::new(format!("err: {self:?}").as_str()).build());
-            }
-        }
+        let template = self.template();
         
         // for now, just return 200 with template body
         Response::builder()
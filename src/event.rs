@@ -0,0 +1,352 @@
+//! Kakao Event API: 사용자 발화 없이 봇이 먼저 보내는 메시지
+//!
+//! 시간 초과, 도전 결과 알림 등에서 쓴다. 실제 전송 방식은 `EventClient` 구현에 따라 다르다.
+//! `KAKAO_REST_API_KEY`, `KAKAO_BOT_ID`가 설정되어 있으면 `KakaoEventClient`로 보내고,
+//! 없으면 로그만 남긴다.
+//!
+//! https://i.kakao.com/docs/skill-event-api
+
+use std::collections::HashMap;
+use std::sync::Arc;
+#[cfg(test)]
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+use crate::{Error, Result};
+use crate::config::Config;
+
+// 블록에서 `{{#event.data.params.message}}`로 꺼내 쓴다
+pub const MESSAGE_EVENT_NAME: &str = "quizbot_message";
+pub const MESSAGE_PARAM: &str = "message";
+// 이미지가 있는 메시지 (첫 문제가 국기 문제일 때 등). 블록에서 이미지 + 텍스트로 보여준다
+pub const IMAGE_MESSAGE_EVENT_NAME: &str = "quizbot_image_message";
+pub const IMAGE_URL_PARAM: &str = "image_url";
+
+// 재시도까지 합쳐서
+const EVENT_API_MAX_ATTEMPTS: u32 = 3;
+const EVENT_API_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+const EVENT_API_TIMEOUT: Duration = Duration::from_secs(5);
+// 요청 사이 최소 간격 (초당 10건)
+const EVENT_API_MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "id")]
+#[serde(rename_all = "camelCase")]
+pub enum EventTarget {
+    // 아직 사용자 한 명에게만 보내는 기능은 없다
+    #[allow(dead_code)]
+    BotUserKey(String),
+    BotGroupKey(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub name: String,
+    pub targets: Vec<EventTarget>,
+    pub params: HashMap<String, String>,
+}
+
+impl Event {
+    /// 방 하나에 텍스트 메시지 하나
+    pub fn group_message(group_key: &str, message: impl Into<String>) -> Self {
+        Self {
+            name: MESSAGE_EVENT_NAME.to_string(),
+            targets: vec![EventTarget::BotGroupKey(group_key.to_string())],
+            params: HashMap::from([(MESSAGE_PARAM.to_string(), message.into())]),
+        }
+    }
+
+    /// 방 하나에 이미지 하나와 텍스트 메시지 하나
+    pub fn group_image_message(group_key: &str, message: impl Into<String>, image_url: impl Into<String>) -> Self {
+        Self {
+            name: IMAGE_MESSAGE_EVENT_NAME.to_string(),
+            targets: vec![EventTarget::BotGroupKey(group_key.to_string())],
+            params: HashMap::from([
+                (MESSAGE_PARAM.to_string(), message.into()),
+                (IMAGE_URL_PARAM.to_string(), image_url.into()),
+            ]),
+        }
+    }
+}
+
+#[async_trait]
+pub trait EventClient: Send + Sync {
+    async fn send(&self, event: Event) -> Result<()>;
+}
+
+/// 설정에 따라 실제로 보내거나 로그만 남긴다
+pub fn event_client(config: &Config) -> Arc<dyn EventClient> {
+    match (&config.KAKAO_REST_API_KEY, &config.KAKAO_BOT_ID) {
+        (Some(rest_api_key), Some(bot_id)) => Arc::new(KakaoEventClient::new(&config.KAKAO_EVENT_API_URL, bot_id, rest_api_key)),
+        _ => Arc::new(LogEventClient),
+    }
+}
+
+/// 보내지 않고 로그만 남긴다. Event API 설정이 없을 때 기본값
+pub struct LogEventClient;
+
+#[async_trait]
+impl EventClient for LogEventClient {
+    async fn send(&self, event: Event) -> Result<()> {
+        info!("{:<12} - (not sent) {} -> {:?}: {:?}", "EVENT", event.name, event.targets, event.params);
+        Ok(())
+    }
+}
+
+/// 보낸 이벤트를 쌓아두기만 한다. 테스트에서 무엇을 보냈는지 확인할 때
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct RecordingEventClient {
+    sent: Arc<Mutex<Vec<Event>>>,
+}
+
+#[cfg(test)]
+impl RecordingEventClient {
+    pub fn sent(&self) -> Vec<Event> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl EventClient for RecordingEventClient {
+    async fn send(&self, event: Event) -> Result<()> {
+        self.sent.lock().unwrap().push(event);
+        Ok(())
+    }
+}
+
+/// `POST {base_url}/v2/bots/{bot_id}/talk`
+pub struct KakaoEventClient {
+    http: reqwest::Client,
+    url: String,
+    rest_api_key: String,
+    // 다음 요청을 보낼 수 있는 시각
+    next_slot: tokio::sync::Mutex<Instant>,
+}
+
+#[derive(Serialize)]
+struct EventRequest<'a> {
+    event: EventRequestEvent<'a>,
+    user: &'a [EventTarget],
+}
+
+#[derive(Serialize)]
+struct EventRequestEvent<'a> {
+    name: &'a str,
+    data: EventRequestData<'a>,
+}
+
+#[derive(Serialize)]
+struct EventRequestData<'a> {
+    params: &'a HashMap<String, String>,
+}
+
+// {"taskId": "...", "status": "SUCCESS"}
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventResponse {
+    task_id: Option<String>,
+    status: String,
+    message: Option<String>,
+}
+
+impl KakaoEventClient {
+    /// `base_url`은 보통 `https://bot-api.kakao.com`. 테스트에서는 로컬 서버로
+    pub fn new(base_url: &str, bot_id: &str, rest_api_key: &str) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(EVENT_API_TIMEOUT)
+                .build()
+                .expect("failed to build http client"),
+            url: format!("{}/v2/bots/{}/talk", base_url.trim_end_matches('/'), bot_id),
+            rest_api_key: rest_api_key.to_string(),
+            next_slot: tokio::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    // 요청 사이 간격을 지킨다. 밀려 있으면 차례대로 기다린다
+    async fn wait_for_slot(&self) {
+        let mut next_slot = self.next_slot.lock().await;
+        tokio::time::sleep_until(*next_slot).await;
+        *next_slot = Instant::now() + EVENT_API_MIN_INTERVAL;
+    }
+
+    /// 다시 보내 볼 만한 실패면 Err(true)
+    async fn try_send(&self, body: &EventRequest<'_>) -> std::result::Result<EventResponse, (bool, String)> {
+        self.wait_for_slot().await;
+
+        let response = self.http.post(&self.url)
+            .header("Authorization", format!("KakaoAK {}", self.rest_api_key))
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| (true, e.to_string()))?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            return Err((true, format!("http {}", status)));
+        }
+        if !status.is_success() {
+            return Err((false, format!("http {}", status)));
+        }
+
+        let response: EventResponse = response.json()
+            .await
+            .map_err(|e| (false, e.to_string()))?;
+        if response.status != "SUCCESS" {
+            return Err((false, format!("{}: {}", response.status, response.message.unwrap_or_default())));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl EventClient for KakaoEventClient {
+    async fn send(&self, event: Event) -> Result<()> {
+        let body = EventRequest {
+            event: EventRequestEvent {
+                name: &event.name,
+                data: EventRequestData { params: &event.params },
+            },
+            user: &event.targets,
+        };
+
+        let mut attempt = 1;
+        loop {
+            match self.try_send(&body).await {
+                Ok(response) => {
+                    debug!("{:<12} - sent {}, task_id: {:?}", "EVENT", event.name, response.task_id);
+                    return Ok(());
+                }
+                Err((retryable, reason)) if retryable && attempt < EVENT_API_MAX_ATTEMPTS => {
+                    warn!("{:<12} - send {} failed ({}/{}): {}", "EVENT", event.name, attempt, EVENT_API_MAX_ATTEMPTS, reason);
+                    tokio::time::sleep(EVENT_API_RETRY_BACKOFF * 2u32.pow(attempt - 1)).await;
+                    attempt += 1;
+                }
+                Err((_, reason)) => return Err(Error::EventSendFail(reason)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use axum::extract::State;
+    use axum::http::HeaderMap;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    // 받은 요청 (도착 시각, Authorization, body)
+    type Received = Arc<Mutex<Vec<(Instant, String, Value)>>>;
+
+    /// Kakao Event API 대신 로컬에서 받는 서버. `statuses`를 차례대로 돌려주고 다 쓰면 200
+    async fn stand_in(statuses: Vec<u16>) -> (String, Received) {
+        let statuses = Arc::new(Mutex::new(VecDeque::from(statuses)));
+        let received: Received = Arc::default();
+
+        let app = Router::new()
+            .route("/v2/bots/:bot_id/talk", post(
+                |State((statuses, received)): State<(Arc<Mutex<VecDeque<u16>>>, Received)>, headers: HeaderMap, Json(body): Json<Value>| async move {
+                    let auth = headers.get("Authorization")
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string();
+                    received.lock().unwrap().push((Instant::now(), auth, body));
+
+                    let status = statuses.lock().unwrap().pop_front().unwrap_or(200);
+                    let status = axum::http::StatusCode::from_u16(status).unwrap();
+                    (status, Json(json!({"taskId": "task", "status": "SUCCESS"})))
+                },
+            ))
+            .with_state((statuses, received.clone()));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (base_url, received)
+    }
+
+    #[tokio::test]
+    async fn sends_event_request_body() {
+        let (base_url, received) = stand_in(vec![]).await;
+        let client = KakaoEventClient::new(&base_url, "bot", "rest-key");
+
+        client.send(Event::group_message("room", "안녕하세요")).await.unwrap();
+
+        let received = received.lock().unwrap();
+        let (_, auth, body) = &received[0];
+        assert_eq!(auth, "KakaoAK rest-key");
+        assert_eq!(body, &json!({
+            "event": {
+                "name": MESSAGE_EVENT_NAME,
+                "data": {"params": {"message": "안녕하세요"}},
+            },
+            "user": [{"type": "botGroupKey", "id": "room"}],
+        }));
+    }
+
+    #[tokio::test]
+    async fn retries_on_429_and_5xx() {
+        let (base_url, received) = stand_in(vec![429, 503]).await;
+        let client = KakaoEventClient::new(&base_url, "bot", "rest-key");
+
+        client.send(Event::group_message("room", "retry")).await.unwrap();
+        assert_eq!(received.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let (base_url, received) = stand_in(vec![500, 500, 500, 500]).await;
+        let client = KakaoEventClient::new(&base_url, "bot", "rest-key");
+
+        let result = client.send(Event::group_message("room", "fail")).await;
+        assert!(matches!(result, Err(Error::EventSendFail(_))));
+        assert_eq!(received.lock().unwrap().len(), EVENT_API_MAX_ATTEMPTS as usize);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (base_url, received) = stand_in(vec![400]).await;
+        let client = KakaoEventClient::new(&base_url, "bot", "rest-key");
+
+        assert!(client.send(Event::group_message("room", "bad")).await.is_err());
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn spaces_out_requests() {
+        let (base_url, received) = stand_in(vec![]).await;
+        let client = Arc::new(KakaoEventClient::new(&base_url, "bot", "rest-key"));
+
+        let sends: Vec<_> = (0..3)
+            .map(|i| {
+                let client = client.clone();
+                tokio::spawn(async move { client.send(Event::group_message("room", i.to_string())).await })
+            })
+            .collect();
+        for send in sends {
+            send.await.unwrap().unwrap();
+        }
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 3);
+        for pair in received.windows(2) {
+            // 서버에 도착한 시각이라 조금 당겨질 수 있다
+            assert!(pair[1].0 - pair[0].0 >= EVENT_API_MIN_INTERVAL - Duration::from_millis(10));
+        }
+    }
+}
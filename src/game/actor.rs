@@ -0,0 +1,203 @@
+//! 방마다 하나씩 도는 게임 task
+//!
+//! `Game`은 이 task만 들고 있고, 밖에서는 `GameHandle`로 명령을 보내서 읽고 바꾼다.
+//! 한 방의 명령(답, 패스, 일시정지, 힌트 등)은 온 순서대로 하나씩 처리되고,
+//! 다른 방끼리는 서로 기다리지 않는다. 실행기 스레드를 막는 잠금도 없다.
+//!
+//! 상태를 바꾸는 명령(`update`)은 이 task 안에서 저장까지 마친 뒤에 답한다.
+//! 그래서 한 방의 저장도 명령 순서대로 일어나고, 예전 상태가 새 상태를 덮어쓰지 않는다.
+//!
+//! 시간이 지나서 끝나야 하는 게임(일시정지 시간 초과, 블리츠 종료)도 이 task가 타이머를 들고 있다가
+//! `Expiry`로 `GameManager`에 알린다. 명령을 처리할 때마다 게임 상태로 타이머를 다시 맞춘다.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+use crate::{Error, Result};
+use crate::config::config;
+use crate::game::state::{Game, GroupKey};
+use crate::game::store::GameStore;
+
+// 한 방에 이보다 많은 명령이 밀리면 보내는 쪽이 기다린다
+const GAME_COMMAND_BUFFER: usize = 64;
+
+type GameFn = Box<dyn FnOnce(&mut Game) + Send>;
+// 저장이 끝난 뒤에 부르는 답장. 저장 결과를 넘긴다
+type ReplyFn = Box<dyn FnOnce(Result<()>) + Send>;
+type UpdateFn = Box<dyn FnOnce(&mut Game) -> ReplyFn + Send>;
+
+/// 게임 task가 끝내야 한다고 알리는 이유
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiry {
+    // 일시정지한 채로 `PAUSED_GAME_TIMEOUT`이 지났다
+    PauseTimeout,
+    // 블리츠 제한 시간이 지났다
+    BlitzEnd,
+}
+
+pub type ExpirySender = mpsc::UnboundedSender<(GroupKey, Expiry)>;
+
+enum Command {
+    Run(GameFn),
+    // 실행하고 저장한 뒤에 답한다
+    Update(UpdateFn),
+    // 지금 문제의 다음 힌트. 저장한 뒤에 답한다
+    Hint(oneshot::Sender<Result<String>>),
+    // 마지막 상태를 돌려주고 끝낸다
+    Stop(oneshot::Sender<Game>),
+}
+
+#[derive(Clone)]
+pub struct GameHandle {
+    group_key: GroupKey,
+    tx: mpsc::Sender<Command>,
+}
+
+impl GameHandle {
+    pub fn spawn(game: Game, store: Arc<dyn GameStore>, expiry_tx: ExpirySender) -> Self {
+        let group_key = game.group_key().clone();
+        let (tx, rx) = mpsc::channel(GAME_COMMAND_BUFFER);
+        tokio::spawn(run(game, store, expiry_tx, rx));
+
+        Self { group_key, tx }
+    }
+
+    /// 같은 게임 task인지 (그 사이에 새로 읽어 온 게임으로 바뀌지 않았는지)
+    pub fn same_game(&self, other: &GameHandle) -> bool {
+        self.tx.same_channel(&other.tx)
+    }
+
+    /// 게임 task에서 `f`를 실행하고 결과를 돌려준다. 읽기만 할 때
+    pub async fn call<R, F>(&self, f: F) -> Result<R>
+        where
+            R: Send + 'static,
+            F: FnOnce(&mut Game) -> R + Send + 'static,
+    {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let command = Command::Run(Box::new(move |game| {
+            let _ = reply_tx.send(f(game));
+        }));
+
+        self.send(command, reply_rx).await
+    }
+
+    /// `call`과 같지만 `f`가 바꾼 상태를 저장한 뒤에 돌려준다.
+    /// 저장소에 더 새 상태가 있으면(다른 인스턴스가 먼저 썼으면) `GameBusy`.
+    /// 그 밖의 저장 실패는 메모리에 남아 있으니 게임을 계속한다
+    pub async fn update<R, F>(&self, f: F) -> Result<R>
+        where
+            R: Send + 'static,
+            F: FnOnce(&mut Game) -> R + Send + 'static,
+    {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let command = Command::Update(Box::new(move |game| {
+            let result = f(game);
+            Box::new(move |saved: Result<()>| {
+                let _ = reply_tx.send(saved.map(|_| result));
+            })
+        }));
+
+        self.send(command, reply_rx).await?
+    }
+
+    /// 지금 상태를 그대로 저장한다 (새 게임)
+    pub async fn save(&self) -> Result<()> {
+        self.update(|_| ()).await
+    }
+
+    pub async fn hint(&self) -> Result<String> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(Command::Hint(reply_tx), reply_rx).await?
+    }
+
+    /// 게임 task를 끝내고 마지막 상태를 돌려준다. 앞에 들어온 명령은 모두 처리한 뒤
+    pub async fn stop(&self) -> Result<Game> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(Command::Stop(reply_tx), reply_rx).await
+    }
+
+    // 이미 끝난 게임이면 `GameNotFound`
+    async fn send<R>(&self, command: Command, reply_rx: oneshot::Receiver<R>) -> Result<R> {
+        self.tx.send(command).await
+            .map_err(|_| Error::GameNotFound(self.group_key.clone()))?;
+        reply_rx.await
+            .map_err(|_| Error::GameNotFound(self.group_key.clone()))
+    }
+}
+
+async fn run(mut game: Game, store: Arc<dyn GameStore>, expiry_tx: ExpirySender, mut rx: mpsc::Receiver<Command>) {
+    let pause_timeout = config().PAUSED_GAME_TIMEOUT;
+    // 같은 이유로 두 번 알리지 않는다. 상태가 바뀌어 타이머가 없어지면 다시 알릴 수 있다
+    let mut notified: Option<Expiry> = None;
+
+    loop {
+        let timer = next_timer(&game, pause_timeout);
+        if timer.is_none() {
+            notified = None;
+        }
+        let timer = timer.filter(|(_, expiry)| Some(*expiry) != notified);
+
+        let command = match timer {
+            Some((deadline, expiry)) => tokio::select! {
+                command = rx.recv() => command,
+                _ = tokio::time::sleep_until(deadline) => {
+                    debug!("{:<12} - {:?}, group_key: {}", "GAME", expiry, game.group_key());
+                    let _ = expiry_tx.send((game.group_key().clone(), expiry));
+                    notified = Some(expiry);
+                    continue;
+                }
+            },
+            None => rx.recv().await,
+        };
+        // handle이 모두 사라져도 끝난다 (분산 모드에서 새로 읽어 온 게임으로 바뀔 때 등)
+        let Some(command) = command else {
+            break;
+        };
+
+        match command {
+            Command::Run(f) => f(&mut game),
+            Command::Update(f) => {
+                let reply = f(&mut game);
+                reply(save(&mut game, store.as_ref()).await);
+            }
+            Command::Hint(reply_tx) => {
+                let hint = game.next_hint();
+                let saved = match hint {
+                    Ok(_) => save(&mut game, store.as_ref()).await,
+                    Err(_) => Ok(()),
+                };
+                let _ = reply_tx.send(saved.and(hint));
+            }
+            Command::Stop(reply_tx) => {
+                let _ = reply_tx.send(game);
+                return;
+            }
+        }
+    }
+    debug!("{:<12} - actor dropped, group_key: {}", "GAME", game.group_key());
+}
+
+/// 지금 상태에서 다음에 울릴 타이머. 일시정지 중에는 블리츠 시간도 멈춘다
+fn next_timer(game: &Game, pause_timeout: Duration) -> Option<(Instant, Expiry)> {
+    if let Some(paused_for) = game.paused_for() {
+        return Some((Instant::now() + pause_timeout.saturating_sub(paused_for), Expiry::PauseTimeout));
+    }
+    let remaining = game.blitz_remaining()?;
+    Some((Instant::now() + remaining, Expiry::BlitzEnd))
+}
+
+async fn save(game: &mut Game, store: &dyn GameStore) -> Result<()> {
+    game.version += 1;
+    match store.save(game).await {
+        Err(Error::GameBusy(group_key)) => Err(Error::GameBusy(group_key)),
+        Err(e) => {
+            warn!("{:<12} - save {} failed: {:?}", "GAME", game.group_key(), e);
+            Ok(())
+        }
+        Ok(()) => Ok(()),
+    }
+}
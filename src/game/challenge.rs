@@ -0,0 +1,266 @@
+//! 방 대 방 도전
+//!
+//! 한 방에서 `도전`으로 도전장(코드)을 만들면, 다른 방에서 `도전 {코드}`로 받는다.
+//! 두 방은 같은 seed로 미리 뽑아 둔 같은 문제를 같은 순서로 풀고,
+//! 둘 다 끝나거나 제한 시간이 지나면 결과를 Event API로 두 방에 알린다.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{Error, Result};
+use crate::game::db::{get_quiz_with_rng, QuizType};
+use crate::game::model::Difficulty;
+use crate::game::state::GroupKey;
+
+// 도전장을 만든 뒤 이 시간 안에 받고 풀어야 한다
+pub const CHALLENGE_WINDOW: Duration = Duration::from_secs(30 * 60);
+
+const CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";  // 헷갈리는 I, O, 0, 1 제외
+const CODE_LEN: usize = 6;
+
+/// "AB3XYZ" 형태인지만 본다 (대소문자 무시)
+pub fn is_challenge_code(s: &str) -> bool {
+    s.len() == CODE_LEN && s.bytes().all(|b| CODE_CHARS.contains(&b.to_ascii_uppercase()))
+}
+
+fn generate_code<R: Rng>(rng: &mut R) -> String {
+    (0..CODE_LEN)
+        .map(|_| CODE_CHARS[rng.gen_range(0..CODE_CHARS.len())] as char)
+        .collect()
+}
+
+/// 같은 seed, 같은 조건이면 같은 문제 목록
+pub fn generate_questions(seed: u64, category: Option<&str>, difficulty: Option<Difficulty>, count: u8) -> Vec<QuizType> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| get_quiz_with_rng(&mut rng, category, difficulty))
+        .collect()
+}
+
+/// 한 방의 결과. 맞힌 문제가 많은 쪽, 같으면 빨리 끝낸 쪽이 이긴다
+#[derive(Debug, Clone, Copy)]
+pub struct RoomResult {
+    pub solved: u32,
+    pub elapsed: Duration,
+}
+
+impl RoomResult {
+    fn describe(&self) -> String {
+        let secs = self.elapsed.as_secs();
+        format!("{}문제 ({}분 {}초)", self.solved, secs / 60, secs % 60)
+    }
+}
+
+pub struct Challenge {
+    pub code: String,
+    pub questions: Arc<Vec<QuizType>>,
+    created_at: Instant,
+    host: GroupKey,
+    guest: Option<GroupKey>,
+    results: HashMap<GroupKey, RoomResult>,
+}
+
+impl Challenge {
+    fn is_expired(&self) -> bool {
+        self.created_at.elapsed() >= CHALLENGE_WINDOW
+    }
+
+    fn rooms(&self) -> Vec<&GroupKey> {
+        std::iter::once(&self.host).chain(self.guest.as_ref()).collect()
+    }
+
+    fn is_done(&self) -> bool {
+        self.guest.is_some() && self.rooms().iter().all(|room| self.results.contains_key(*room))
+    }
+
+    /// 방마다 보낼 결과 메시지. 결과가 없는 방은 기권 처리
+    pub fn announcements(&self) -> Vec<(GroupKey, String)> {
+        let Some(guest) = &self.guest else {
+            return vec![(
+                self.host.clone(),
+                format!("⌛ 도전 {}: 시간 안에 도전을 받은 방이 없어요", self.code),
+            )];
+        };
+
+        [(&self.host, guest), (guest, &self.host)].into_iter()
+            .map(|(ours, theirs)| {
+                let ours_result = self.results.get(ours);
+                let theirs_result = self.results.get(theirs);
+                let describe = |result: Option<&RoomResult>| result
+                    .map(RoomResult::describe)
+                    .unwrap_or("기권 (시간 초과)".to_string());
+
+                let verdict = match (ours_result, theirs_result) {
+                    (Some(a), Some(b)) => match a.solved.cmp(&b.solved).then(b.elapsed.cmp(&a.elapsed)) {
+                        std::cmp::Ordering::Greater => "🏆 우리 방 승리!",
+                        std::cmp::Ordering::Less => "😢 상대 방 승리",
+                        std::cmp::Ordering::Equal => "🤝 무승부!",
+                    },
+                    (Some(_), None) => "🏆 우리 방 승리!",
+                    (None, Some(_)) => "😢 상대 방 승리",
+                    (None, None) => "🤝 두 방 모두 끝내지 못했어요",
+                };
+
+                let message = format!(
+                    "⚔️ 도전 {} 결과\n우리 방: {}\n상대 방: {}\n{}",
+                    self.code, describe(ours_result), describe(theirs_result), verdict,
+                );
+                (ours.clone(), message)
+            })
+            .collect()
+    }
+}
+
+/// 진행 중인 도전 목록. 끝나거나 만료되면 빠진다
+#[derive(Clone, Default)]
+pub struct ChallengeBoard {
+    challenges: Arc<Mutex<HashMap<String, Challenge>>>,
+}
+
+impl ChallengeBoard {
+    /// 새 도전장. 코드와 문제 목록을 돌려준다
+    pub async fn create(&self, host: GroupKey, category: Option<&str>, difficulty: Option<Difficulty>, count: u8) -> (String, Arc<Vec<QuizType>>) {
+        // 잠금을 기다리는 동안 들고 있으니 Send인 rng로
+        let mut rng = StdRng::from_entropy();
+        let seed: u64 = rng.gen();
+        let questions = Arc::new(generate_questions(seed, category, difficulty, count));
+
+        let mut challenges = self.challenges.lock().await;
+        let code = loop {
+            let code = generate_code(&mut rng);
+            if !challenges.contains_key(&code) {
+                break code;
+            }
+        };
+
+        debug!("{:<12} - created {}, host: {}, seed: {}", "CHALLENGE", code, host, seed);
+        challenges.insert(code.clone(), Challenge {
+            code: code.clone(),
+            questions: questions.clone(),
+            created_at: Instant::now(),
+            host,
+            guest: None,
+            results: HashMap::new(),
+        });
+
+        (code, questions)
+    }
+
+    pub async fn accept(&self, code: &str, guest: GroupKey) -> Result<Arc<Vec<QuizType>>> {
+        let code = code.to_uppercase();
+        let mut challenges = self.challenges.lock().await;
+        let challenge = challenges.get_mut(&code)
+            .filter(|challenge| !challenge.is_expired())
+            .ok_or(Error::ChallengeNotFound(code.clone()))?;
+
+        if challenge.host == guest {
+            return Err(Error::ChallengeOwnRoom(code));
+        }
+        if challenge.guest.as_ref().is_some_and(|g| *g != guest) {
+            return Err(Error::ChallengeAlreadyAccepted(code));
+        }
+
+        challenge.guest = Some(guest);
+        Ok(challenge.questions.clone())
+    }
+
+    /// 방 하나가 끝났을 때. 두 방 다 끝났으면 도전을 정리하고 결과 메시지를 돌려준다.
+    /// 이미 만료됐거나 재시작해서 도전이 없으면 `ChallengeNotFound`
+    pub async fn record_result(&self, code: &str, group_key: &GroupKey, result: RoomResult) -> Result<Option<Vec<(GroupKey, String)>>> {
+        let mut challenges = self.challenges.lock().await;
+        let challenge = challenges.get_mut(code)
+            .ok_or(Error::ChallengeNotFound(code.to_string()))?;
+        challenge.results.insert(group_key.clone(), result);
+
+        if !challenge.is_done() {
+            return Ok(None);
+        }
+        Ok(challenges.remove(code).map(|challenge| challenge.announcements()))
+    }
+
+    /// 제한 시간이 지났는데 아직 남아 있으면 정리하고 결과 메시지를 돌려준다
+    pub async fn expire(&self, code: &str) -> Option<Vec<(GroupKey, String)>> {
+        let mut challenges = self.challenges.lock().await;
+        challenges.remove(code).map(|challenge| challenge.announcements())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{EventTarget, RecordingEventClient, MESSAGE_PARAM};
+    use crate::game::db::MATH_CATEGORY;
+    use crate::game::state::{AnswerResult, GameManager};
+    use crate::game::state::tests::{math_options, test_manager_with_events, wait_until};
+    use crate::game::store::InMemoryGameStore;
+
+    fn describe(questions: &[QuizType]) -> Vec<(String, String)> {
+        questions.iter()
+            .map(|quiz| (quiz.as_question().info_before(""), quiz.as_question().reveal_answer()))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_same_questions() {
+        let questions = generate_questions(7, Some(MATH_CATEGORY), Some(Difficulty::Hard), 5);
+        assert_eq!(questions.len(), 5);
+        assert_eq!(describe(&questions), describe(&generate_questions(7, Some(MATH_CATEGORY), Some(Difficulty::Hard), 5)));
+        assert_ne!(describe(&questions), describe(&generate_questions(8, Some(MATH_CATEGORY), Some(Difficulty::Hard), 5)));
+    }
+
+    // 남은 문제를 모두 맞힌다
+    async fn solve(gm: &GameManager, group_key: &GroupKey, mut quiz: QuizType) {
+        loop {
+            let QuizType::Math(math) = &quiz else {
+                unreachable!();
+            };
+            let AnswerResult::Correct { next_quiz, .. } = gm.try_answer_inmemory("user", group_key, &math.answer.to_string()).await.unwrap() else {
+                panic!("not correct");
+            };
+            match next_quiz {
+                Some(next) => quiz = *next,
+                None => return,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn both_rooms_get_the_result() {
+        let events = RecordingEventClient::default();
+        let gm = test_manager_with_events(Arc::new(InMemoryGameStore::default()), false, Arc::new(events.clone()));
+        let (host, guest) = ("host".to_string(), "guest".to_string());
+
+        let (code, host_game) = gm.create_challenge(host.clone(), math_options()).await.unwrap();
+        let guest_game = gm.accept_challenge(guest.clone(), &code.to_lowercase()).await.unwrap();
+        assert!(matches!(gm.accept_challenge("third".to_string(), &code).await, Err(Error::ChallengeAlreadyAccepted(_))));
+        assert_eq!(
+            describe(std::slice::from_ref(&host_game.current_quiz)),
+            describe(std::slice::from_ref(&guest_game.current_quiz)),
+        );
+
+        // 상대 방은 첫 문제를 넘겨서 하나 덜 맞힌다
+        solve(&gm, &host, host_game.current_quiz).await;
+        assert!(events.sent().is_empty());
+        let next = gm.skip_question(&guest).await.unwrap().next_quiz.unwrap();
+        solve(&gm, &guest, next).await;
+
+        wait_until(|| async { events.sent().len() == 2 }).await;
+        let messages: HashMap<GroupKey, String> = events.sent().into_iter()
+            .map(|event| {
+                let [EventTarget::BotGroupKey(group_key)] = &event.targets[..] else {
+                    panic!("unexpected target");
+                };
+                (group_key.clone(), event.params[MESSAGE_PARAM].clone())
+            })
+            .collect();
+        assert!(messages[&host].starts_with(&format!("⚔️ 도전 {} 결과", code)));
+        assert!(messages[&host].contains("🏆 우리 방 승리!"));
+        assert!(messages[&guest].contains("😢 상대 방 승리"));
+    }
+}
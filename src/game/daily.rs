@@ -0,0 +1,576 @@
+//! 오늘의 퀴즈: 날짜로 정해지는 N문제. 모든 방, 모든 사용자에게 같은 문제가 나온다
+//!
+//! 사용자마다 하루 한 번만 풀 수 있고, 문제마다 답도 한 번만 낼 수 있다 (틀리면 다음 문제로).
+//! 다 풀면 맞힌 개수와 걸린 시간이 그날의 순위표에 들어간다.
+//!
+//! 시작(시작 표시 + 시도 저장)과 답(읽은 시도가 그대로일 때만 다음 문제로)은 각각 한 번에 처리한다.
+//! 같은 문제에 답이 두 번 몰리면 먼저 처리된 답만 센다.
+//!
+//! Redis key structure
+//! ---
+//! - key: `daily:{date}:attempted` (SET): 그날 시작한 user_id
+//! - key: `daily:{date}:attempt:{user_id}` / value: `{index}:{correct}:{started_at_ms}` (푸는 중. 그날이 끝나면 만료)
+//! - key: `daily:{date}:results` (HASH) / field: `{user_id}` / value: `{correct}:{elapsed_ms}`
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use redis::AsyncCommands;
+use tracing::debug;
+
+use crate::{Error, Result};
+use crate::clock::{end_of_day, format_days, Clock};
+use crate::config::GameStoreConfig;
+use crate::conn::RedisConnectionPool;
+use crate::game::db::{get_quiz_with_rng, QuizType, CAPITAL_CATEGORY, FLAG_CATEGORY, MATH_CATEGORY};
+
+const REDIS_DAILY_KEY_PREFIX: &str = "daily:";
+// 지난 순위표는 일주일쯤 남겨둔다
+const REDIS_DAILY_TTL_SECS: i64 = 8 * 24 * 60 * 60;
+
+// 푸는 중이면 그 시도를, 처음이면 시작 표시와 새 시도를 함께 쓰고 돌려준다. 이미 끝냈으면 ""
+// KEYS: attempted, attempt / ARGV: user_id, 새 시도, 시도 TTL(초), attempted TTL(초)
+const REDIS_START_SCRIPT: &str = r#"
+local current = redis.call("get", KEYS[2])
+if current then
+    return current
+end
+if redis.call("sadd", KEYS[1], ARGV[1]) == 0 then
+    return ""
+end
+redis.call("expire", KEYS[1], ARGV[4])
+redis.call("set", KEYS[2], ARGV[2], "EX", ARGV[3])
+return ARGV[2]
+"#;
+
+// 읽었던 시도가 그대로일 때만 다음 문제로. 기록이 있으면(다 풀었으면) 시도를 지우고 기록을 남긴다
+// KEYS: attempt, results / ARGV: 읽었던 시도, 다음 시도, 시도 TTL(초), user_id, 기록 (없으면 ""), results TTL(초)
+const REDIS_ADVANCE_SCRIPT: &str = r#"
+if redis.call("get", KEYS[1]) ~= ARGV[1] then
+    return 0
+end
+if ARGV[5] == "" then
+    redis.call("set", KEYS[1], ARGV[2], "EX", ARGV[3])
+else
+    redis.call("del", KEYS[1])
+    redis.call("hset", KEYS[2], ARGV[4], ARGV[5])
+    redis.call("expire", KEYS[2], ARGV[6])
+end
+return 1
+"#;
+
+// 문제마다 카테고리. None은 전체 텍스트 퀴즈
+const DAILY_CATEGORIES: [Option<&str>; 5] = [
+    None,
+    Some(FLAG_CATEGORY),
+    Some(CAPITAL_CATEGORY),
+    Some(MATH_CATEGORY),
+    None,
+];
+// 날짜만으로 seed를 만들면 도전 seed와 겹칠 수 있어서 섞는다
+const DAILY_SEED_SALT: u64 = 0x5EED_DA11;
+
+pub const DAILY_LEADERBOARD_SIZE: usize = 10;
+
+/// 날짜(`Clock::today`)가 같으면 같은 문제 목록
+pub fn daily_questions(day: i64) -> Vec<QuizType> {
+    let mut rng = StdRng::seed_from_u64(day as u64 ^ DAILY_SEED_SALT);
+    DAILY_CATEGORIES.iter()
+        .map(|category| get_quiz_with_rng(&mut rng, *category, None))
+        .collect()
+}
+
+/// 푸는 중인 사람. 재시작하거나 다른 인스턴스로 가도 이어서 풀 수 있도록 Redis에 둔다
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempt {
+    index: usize,
+    correct: u32,
+    started_at_ms: u64,
+}
+
+impl Attempt {
+    fn new(now: SystemTime) -> Self {
+        Self {
+            index: 0,
+            correct: 0,
+            started_at_ms: now.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+        }
+    }
+
+    fn to_redis_value(&self) -> String {
+        format!("{}:{}:{}", self.index, self.correct, self.started_at_ms)
+    }
+
+    fn from_redis_value(value: &str) -> Option<Self> {
+        let mut parts = value.splitn(3, ':');
+        Some(Self {
+            index: parts.next()?.parse().ok()?,
+            correct: parts.next()?.parse().ok()?,
+            started_at_ms: parts.next()?.parse().ok()?,
+        })
+    }
+
+    fn current(&self, questions: &[QuizType]) -> DailyQuestion {
+        DailyQuestion {
+            quiz: questions[self.index].clone(),
+            round_header: format!("[오늘의 퀴즈 {}/{}]", self.index + 1, questions.len()),
+        }
+    }
+
+    /// 지금 문제를 채점하고 다음 문제로. 마지막 문제였으면 기록까지
+    fn answer(&mut self, user_id: &str, questions: &[QuizType], answer: &str, now: SystemTime) -> DailyAnswerResult {
+        let quiz = &questions[self.index];
+        let correct = quiz.is_correct_answer(answer);
+        let revealed_answer = quiz.as_question().reveal_answer();
+        if correct {
+            self.correct += 1;
+        }
+        self.index += 1;
+
+        let total = questions.len();
+        let started_at = UNIX_EPOCH + Duration::from_millis(self.started_at_ms);
+        let finished = (self.index >= total).then(|| DailyRecord {
+            user_id: user_id.to_string(),
+            correct: self.correct,
+            elapsed: now.duration_since(started_at).unwrap_or_default(),
+        });
+        let next = finished.is_none().then(|| self.current(questions));
+
+        DailyAnswerResult { correct, revealed_answer, next, finished, total }
+    }
+}
+
+/// 그날이 끝날 때까지 남은 초. 푸는 중인 기록은 그때 사라진다
+fn seconds_until_end_of_day(clock: &dyn Clock) -> u64 {
+    let day = clock.today();
+    end_of_day(day).duration_since(clock.now()).unwrap_or_default().as_secs().max(1)
+}
+
+// (day, 문제 목록)
+type CachedQuestions = Option<(i64, Arc<Vec<QuizType>>)>;
+
+pub struct DailyQuestion {
+    pub quiz: QuizType,
+    pub round_header: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DailyRecord {
+    pub user_id: String,
+    pub correct: u32,
+    pub elapsed: Duration,
+}
+
+impl DailyRecord {
+    fn to_redis_value(&self) -> String {
+        format!("{}:{}", self.correct, self.elapsed.as_millis())
+    }
+
+    fn from_redis_value(user_id: String, value: &str) -> Option<Self> {
+        let (correct, elapsed_ms) = value.split_once(':')?;
+        Some(Self {
+            user_id,
+            correct: correct.parse().ok()?,
+            elapsed: Duration::from_millis(elapsed_ms.parse().ok()?),
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        let secs = self.elapsed.as_secs();
+        format!("{}문제 ({}분 {}초)", self.correct, secs / 60, secs % 60)
+    }
+}
+
+pub struct DailyAnswerResult {
+    pub correct: bool,
+    pub revealed_answer: String,
+    // 다 풀었으면 None
+    pub next: Option<DailyQuestion>,
+    pub finished: Option<DailyRecord>,
+    pub total: usize,
+}
+
+pub struct DailyLeaderboard {
+    pub date: String,
+    // 많이 맞힌 순, 같으면 빨리 푼 순
+    pub records: Vec<DailyRecord>,
+}
+
+impl DailyLeaderboard {
+    /// 1등부터. 기록이 없으면 None
+    pub fn rank_of(&self, user_id: &str) -> Option<usize> {
+        self.records.iter().position(|record| record.user_id == user_id).map(|index| index + 1)
+    }
+}
+
+#[async_trait]
+pub trait DailyStore: Send + Sync {
+    /// 푸는 중이면 그 시도, 처음이면 `attempt`로 시작해서 돌려준다. 오늘 이미 끝냈으면 None.
+    /// 시작 표시와 시도는 함께 쓴다 (시작 표시만 남아 그날 못 풀게 되지 않도록)
+    async fn start(&self, day: i64, user_id: &str, attempt: &Attempt, ttl_secs: u64) -> Result<Option<Attempt>>;
+    async fn attempt(&self, day: i64, user_id: &str) -> Result<Option<Attempt>>;
+    /// `read`를 읽은 뒤로 그대로일 때만 `next`로 바꾼다. `record`가 있으면 시도를 지우고 기록을 남긴다.
+    /// 그 사이 다른 답이 먼저 처리됐으면 false
+    async fn advance(&self, day: i64, user_id: &str, read: &Attempt, next: &Attempt, record: Option<&DailyRecord>, ttl_secs: u64) -> Result<bool>;
+    async fn records(&self, day: i64) -> Result<Vec<DailyRecord>>;
+}
+
+/// `GAME_STORE`가 메모리면 오늘의 퀴즈도 메모리에 (재시작하면 사라진다). 아니면 Redis
+pub fn daily_store(store_config: &GameStoreConfig, pool: RedisConnectionPool) -> Arc<dyn DailyStore> {
+    match store_config {
+        GameStoreConfig::Memory => Arc::new(InMemoryDailyStore::default()),
+        GameStoreConfig::Redis | GameStoreConfig::File(_) => Arc::new(RedisDailyStore { pool }),
+    }
+}
+
+pub struct RedisDailyStore {
+    pool: RedisConnectionPool,
+}
+
+impl RedisDailyStore {
+    fn redis_key(day: i64, name: &str) -> String {
+        format!("{}{}:{}", REDIS_DAILY_KEY_PREFIX, format_days(day), name)
+    }
+
+    fn attempt_key(day: i64, user_id: &str) -> String {
+        format!("{}{}:attempt:{}", REDIS_DAILY_KEY_PREFIX, format_days(day), user_id)
+    }
+}
+
+#[async_trait]
+impl DailyStore for RedisDailyStore {
+    async fn start(&self, day: i64, user_id: &str, attempt: &Attempt, ttl_secs: u64) -> Result<Option<Attempt>> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let attempt_key = Self::attempt_key(day, user_id);
+        let current: String = redis::Script::new(REDIS_START_SCRIPT)
+            .key(Self::redis_key(day, "attempted"))
+            .key(&attempt_key)
+            .arg(user_id)
+            .arg(attempt.to_redis_value())
+            .arg(ttl_secs)
+            .arg(REDIS_DAILY_TTL_SECS)
+            .invoke_async(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(attempt_key))?;
+
+        Ok(Attempt::from_redis_value(&current))
+    }
+
+    async fn attempt(&self, day: i64, user_id: &str) -> Result<Option<Attempt>> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let attempt_key = Self::attempt_key(day, user_id);
+        let attempt: Option<String> = conn.get(&attempt_key)
+            .await
+            .map_err(|_| Error::RedisCommandFail(attempt_key))?;
+        Ok(attempt.as_deref().and_then(Attempt::from_redis_value))
+    }
+
+    async fn advance(&self, day: i64, user_id: &str, read: &Attempt, next: &Attempt, record: Option<&DailyRecord>, ttl_secs: u64) -> Result<bool> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let attempt_key = Self::attempt_key(day, user_id);
+        let advanced: i64 = redis::Script::new(REDIS_ADVANCE_SCRIPT)
+            .key(&attempt_key)
+            .key(Self::redis_key(day, "results"))
+            .arg(read.to_redis_value())
+            .arg(next.to_redis_value())
+            .arg(ttl_secs)
+            .arg(user_id)
+            .arg(record.map(DailyRecord::to_redis_value).unwrap_or_default())
+            .arg(REDIS_DAILY_TTL_SECS)
+            .invoke_async(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(attempt_key))?;
+
+        Ok(advanced == 1)
+    }
+
+    async fn records(&self, day: i64) -> Result<Vec<DailyRecord>> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let redis_key = Self::redis_key(day, "results");
+        let results: HashMap<String, String> = conn.hgetall(&redis_key)
+            .await
+            .map_err(|_| Error::RedisCommandFail(redis_key))?;
+
+        Ok(results.into_iter()
+            .filter_map(|(user_id, value)| DailyRecord::from_redis_value(user_id, &value))
+            .collect())
+    }
+}
+
+#[derive(Default)]
+struct InMemoryDays {
+    // (day, user_id)
+    attempted: HashSet<(i64, String)>,
+    attempts: HashMap<(i64, String), Attempt>,
+    results: HashMap<i64, HashMap<String, DailyRecord>>,
+}
+
+/// 재시작하면 사라진다. 키에 날짜가 들어 있어서 지난 시도는 만료시키지 않아도 쓰이지 않는다
+#[derive(Default)]
+pub struct InMemoryDailyStore {
+    days: Mutex<InMemoryDays>,
+}
+
+#[async_trait]
+impl DailyStore for InMemoryDailyStore {
+    async fn start(&self, day: i64, user_id: &str, attempt: &Attempt, _ttl_secs: u64) -> Result<Option<Attempt>> {
+        let mut days = self.days.lock().unwrap();
+        let key = (day, user_id.to_string());
+        if let Some(current) = days.attempts.get(&key) {
+            return Ok(Some(current.clone()));
+        }
+        if !days.attempted.insert(key.clone()) {
+            return Ok(None);
+        }
+        days.attempts.insert(key, attempt.clone());
+        Ok(Some(attempt.clone()))
+    }
+
+    async fn attempt(&self, day: i64, user_id: &str) -> Result<Option<Attempt>> {
+        Ok(self.days.lock().unwrap().attempts.get(&(day, user_id.to_string())).cloned())
+    }
+
+    async fn advance(&self, day: i64, user_id: &str, read: &Attempt, next: &Attempt, record: Option<&DailyRecord>, _ttl_secs: u64) -> Result<bool> {
+        let mut days = self.days.lock().unwrap();
+        let key = (day, user_id.to_string());
+        if days.attempts.get(&key) != Some(read) {
+            return Ok(false);
+        }
+        match record {
+            Some(record) => {
+                days.attempts.remove(&key);
+                days.results.entry(day).or_default().insert(user_id.to_string(), record.clone());
+            }
+            None => {
+                days.attempts.insert(key, next.clone());
+            }
+        }
+        Ok(true)
+    }
+
+    async fn records(&self, day: i64) -> Result<Vec<DailyRecord>> {
+        Ok(self.days.lock().unwrap().results.get(&day)
+            .map(|results| results.values().cloned().collect())
+            .unwrap_or_default())
+    }
+}
+
+#[derive(Clone)]
+pub struct DailyQuiz {
+    store: Arc<dyn DailyStore>,
+    clock: Arc<dyn Clock>,
+    questions: Arc<Mutex<CachedQuestions>>,
+}
+
+impl DailyQuiz {
+    pub fn new(store: Arc<dyn DailyStore>, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            store,
+            clock,
+            questions: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn questions_for(&self, day: i64) -> Arc<Vec<QuizType>> {
+        let mut cached = self.questions.lock().unwrap();
+        match cached.as_ref() {
+            Some((cached_day, questions)) if *cached_day == day => questions.clone(),
+            _ => {
+                let questions = Arc::new(daily_questions(day));
+                *cached = Some((day, questions.clone()));
+                questions
+            }
+        }
+    }
+
+    /// 푸는 중이면 지금 문제를 다시 보여주고, 아니면 새로 시작한다 (하루 한 번)
+    pub async fn start(&self, user_id: &str) -> Result<DailyQuestion> {
+        let day = self.clock.today();
+        let questions = self.questions_for(day);
+
+        let attempt = Attempt::new(self.clock.now());
+        let attempt = self.store.start(day, user_id, &attempt, seconds_until_end_of_day(self.clock.as_ref()))
+            .await?
+            .ok_or(Error::DailyAlreadyAttempted)?;
+        debug!("{:<12} - start {}, user_id: {}, index: {}", "DAILY", format_days(day), user_id, attempt.index);
+
+        Ok(attempt.current(&questions))
+    }
+
+    /// 문제마다 한 번만 답할 수 있다. 마지막 문제면 기록을 남긴다.
+    /// 날짜가 바뀌면 어제 시도는 무효 (키에 날짜가 들어 있다)
+    pub async fn answer(&self, user_id: &str, answer: &str) -> Result<DailyAnswerResult> {
+        let day = self.clock.today();
+        let questions = self.questions_for(day);
+
+        let attempt = self.store.attempt(day, user_id)
+            .await?
+            .filter(|attempt| attempt.index < questions.len())
+            .ok_or(Error::DailyNotStarted)?;
+        self.answer_attempt(day, &questions, user_id, attempt, answer).await
+    }
+
+    // `read`를 읽은 뒤로 같은 문제에 다른 답이 먼저 처리됐으면 이 답은 세지 않는다
+    async fn answer_attempt(&self, day: i64, questions: &[QuizType], user_id: &str, read: Attempt, answer: &str) -> Result<DailyAnswerResult> {
+        let mut next = read.clone();
+        let result = next.answer(user_id, questions, answer, self.clock.now());
+        let ttl_secs = seconds_until_end_of_day(self.clock.as_ref());
+        if !self.store.advance(day, user_id, &read, &next, result.finished.as_ref(), ttl_secs).await? {
+            return Err(Error::DailyAlreadyAnswered);
+        }
+        Ok(result)
+    }
+
+    /// 오늘 순위표
+    pub async fn leaderboard(&self) -> Result<DailyLeaderboard> {
+        let day = self.clock.today();
+        let mut records = self.store.records(day).await?;
+        records.sort_by(|a, b| b.correct.cmp(&a.correct)
+            .then(a.elapsed.cmp(&b.elapsed))
+            .then(a.user_id.cmp(&b.user_id)));
+
+        Ok(DailyLeaderboard {
+            date: format_days(day),
+            records,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::generator::generate_math_quiz;
+    use crate::game::model::Difficulty;
+
+    // 2024-01-01 00:00 KST
+    const NEW_YEAR_KST_SECS: u64 = 19_723 * 24 * 60 * 60 - 9 * 60 * 60;
+
+    struct FixedClock(Mutex<SystemTime>);
+
+    impl FixedClock {
+        fn at(secs: u64) -> Self {
+            Self(Mutex::new(UNIX_EPOCH + Duration::from_secs(secs)))
+        }
+
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for FixedClock {
+        fn now(&self) -> SystemTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn math_questions(count: usize) -> Vec<QuizType> {
+        let mut rng = StdRng::seed_from_u64(1);
+        (0..count)
+            .map(|_| QuizType::Math(generate_math_quiz(&mut rng, Difficulty::default())))
+            .collect()
+    }
+
+    fn answer_of(quiz: &QuizType) -> String {
+        match quiz {
+            QuizType::Math(quiz) => quiz.answer.to_string(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn attempt_is_scored_with_the_clock() {
+        let clock = FixedClock::at(NEW_YEAR_KST_SECS + 60);
+        let questions = math_questions(3);
+        let mut attempt = Attempt::new(clock.now());
+
+        let result = attempt.answer("user", &questions, &answer_of(&questions[0]), clock.now());
+        assert!(result.correct);
+        assert!(result.finished.is_none());
+        assert_eq!(result.next.unwrap().round_header, "[오늘의 퀴즈 2/3]");
+
+        clock.advance(Duration::from_secs(30));
+        let result = attempt.answer("user", &questions, "틀린 답", clock.now());
+        assert!(!result.correct);
+
+        // 다시 읽어 와도 이어서
+        let mut attempt = Attempt::from_redis_value(&attempt.to_redis_value()).unwrap();
+        assert_eq!(attempt.index, 2);
+
+        clock.advance(Duration::from_secs(60));
+        let result = attempt.answer("user", &questions, &answer_of(&questions[2]), clock.now());
+        let record = result.finished.unwrap();
+        assert!(result.next.is_none());
+        assert_eq!(record.correct, 2);
+        assert_eq!(record.elapsed, Duration::from_secs(90));
+    }
+
+    #[test]
+    fn attempt_expires_at_the_end_of_the_day() {
+        let clock = FixedClock::at(NEW_YEAR_KST_SECS);
+        assert_eq!(format_days(clock.today()), "2024-01-01");
+        assert_eq!(seconds_until_end_of_day(&clock), 24 * 60 * 60);
+
+        clock.advance(Duration::from_secs(24 * 60 * 60 - 60));
+        assert_eq!(seconds_until_end_of_day(&clock), 60);
+
+        // 자정이 지나면 다음 날 키
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(format_days(clock.today()), "2024-01-02");
+        assert_eq!(RedisDailyStore::attempt_key(clock.today(), "user"), "daily:2024-01-02:attempt:user");
+    }
+
+    #[tokio::test]
+    async fn start_resumes_and_refuses_after_finishing() {
+        let store = InMemoryDailyStore::default();
+        let clock = FixedClock::at(NEW_YEAR_KST_SECS);
+        let day = clock.today();
+        let first = Attempt::new(clock.now());
+        assert_eq!(store.start(day, "user", &first, 60).await.unwrap(), Some(first.clone()));
+
+        // 푸는 중에 다시 시작하면 이어서
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(store.start(day, "user", &Attempt::new(clock.now()), 60).await.unwrap(), Some(first.clone()));
+
+        // 다 풀고 나면 그날은 다시 시작할 수 없다
+        let record = DailyRecord { user_id: "user".to_string(), correct: 1, elapsed: Duration::from_secs(10) };
+        assert!(store.advance(day, "user", &first, &first, Some(&record), 60).await.unwrap());
+        assert_eq!(store.start(day, "user", &Attempt::new(clock.now()), 60).await.unwrap(), None);
+        assert_eq!(store.records(day).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn same_question_is_answered_once() {
+        let store = Arc::new(InMemoryDailyStore::default());
+        let clock = Arc::new(FixedClock::at(NEW_YEAR_KST_SECS));
+        let daily = DailyQuiz::new(store.clone(), clock.clone());
+        let day = clock.today();
+        let questions = math_questions(3);
+        let read = store.start(day, "user", &Attempt::new(clock.now()), 60).await.unwrap().unwrap();
+
+        // 두 메시지가 같은 시도를 읽었다
+        let first = daily.answer_attempt(day, &questions, "user", read.clone(), &answer_of(&questions[0])).await.unwrap();
+        assert!(first.correct);
+        let second = daily.answer_attempt(day, &questions, "user", read, &answer_of(&questions[0])).await;
+        assert!(matches!(second, Err(Error::DailyAlreadyAnswered)));
+
+        // 첫 문제는 한 번만 셌다
+        let attempt = store.attempt(day, "user").await.unwrap().unwrap();
+        assert_eq!((attempt.index, attempt.correct), (1, 1));
+    }
+}
@@ -3,11 +3,15 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::OnceLock;
 use rand::Rng;
+use rand::seq::SliceRandom;
 use phf::{phf_set, Set};
+use serde::{Deserialize, Serialize};
 
 use tracing::warn;
 
-use crate::game::model::{FlagQuiz, Quiz};
+use crate::game::hangul;
+use crate::game::generator::{generate_math_quiz, MathQuiz};
+use crate::game::model::{CapitalQuiz, ChosungQuiz, Difficulty, FlagChoiceQuiz, FlagQuiz, ImageQuiz, Question, Quiz};
 
 // const QUIZ_DB_DIR: &str = "data";
 // 임시로 배포용으로 절대경로 넣기
@@ -22,30 +26,179 @@ pub static QUIZ_CATEGORIES: Set<&'static str> = phf_set! {
     "고사성어",
 };
 
+// 별도 db를 쓰는 특수 카테고리
+pub const FLAG_CATEGORY: &str = "국기";
+// 국기 db 기반: 나라 -> 수도, 수도 -> 나라, 나라 -> 국기 고르기
+pub const CAPITAL_CATEGORY: &str = "수도";
+pub const CAPITAL_REVERSE_CATEGORY: &str = "나라";
+pub const FLAG_CHOICE_CATEGORY: &str = "국기찾기";
+// 생성형
+pub const MATH_CATEGORY: &str = "수학";
+
+// 국기 고르기 보기 개수
+const FLAG_CHOICE_COUNT: usize = 4;
+
+fn is_flag_db_category(category: &str) -> bool {
+    matches!(category, FLAG_CATEGORY | CAPITAL_CATEGORY | CAPITAL_REVERSE_CATEGORY | FLAG_CHOICE_CATEGORY)
+}
+// "초성" 또는 "초성 {카테고리}"
+pub const CHOSUNG_CATEGORY: &str = "초성";
+
+/// `시작 {카테고리}` 에 쓸 수 있는 카테고리인지
+pub fn is_valid_category(category: &str) -> bool {
+    match chosung_sub_category(category) {
+        Some(None) => true,
+        Some(Some(sub_category)) => QUIZ_CATEGORIES.contains(sub_category) || sub_category == FLAG_CATEGORY,
+        None => QUIZ_CATEGORIES.contains(category)
+            || is_flag_db_category(category)
+            || category == MATH_CATEGORY
+            || image_quiz_db().has_set(category),
+    }
+}
+
+/// 난이도 데이터가 있는 카테고리인지. 텍스트 퀴즈(카테고리 없음 포함)와 수학만
+pub fn supports_difficulty(category: Option<&str>) -> bool {
+    match category {
+        None => true,
+        Some(category) => QUIZ_CATEGORIES.contains(category) || category == MATH_CATEGORY,
+    }
+}
+
+/// "초성" -> Some(None), "초성 상식" -> Some(Some("상식")), 초성 카테고리가 아니면 None
+fn chosung_sub_category(category: &str) -> Option<Option<&str>> {
+    if category == CHOSUNG_CATEGORY {
+        return Some(None);
+    }
+
+    category.strip_prefix(CHOSUNG_CATEGORY)
+        .and_then(|rest| rest.strip_prefix(' '))
+        .map(|rest| Some(rest.trim()))
+}
+
+/// 선택된 카테고리에 맞는 문제 하나. 카테고리가 없으면 전체 텍스트 퀴즈에서 랜덤
+/// 난이도는 텍스트/수학 문제에만 적용되고, 해당 난이도 문제가 없으면 무시한다
+pub fn get_random_quiz(category: Option<&str>, difficulty: Option<Difficulty>) -> QuizType {
+    get_quiz_with_rng(&mut rand::thread_rng(), category, difficulty)
+}
+
+/// `get_random_quiz`와 같지만 rng를 받는다. 같은 seed면 같은 문제 (도전, 오늘의 퀴즈)
+pub fn get_quiz_with_rng<R: Rng>(rng: &mut R, category: Option<&str>, difficulty: Option<Difficulty>) -> QuizType {
+    let Some(category) = category else {
+        return QuizType::Simple(quiz_db().get_any_random_quiz(rng, difficulty).clone());
+    };
+
+    match category {
+        FLAG_CATEGORY => return QuizType::Flag(flag_quiz_db().get_random_flag_quiz(rng).clone()),
+        CAPITAL_CATEGORY | CAPITAL_REVERSE_CATEGORY => {
+            // 수도 데이터가 하나도 없으면 그냥 국기 문제
+            let reverse = category == CAPITAL_REVERSE_CATEGORY;
+            return match flag_quiz_db().get_random_capital_quiz(rng, reverse) {
+                Some(quiz) => QuizType::Capital(quiz),
+                None => QuizType::Flag(flag_quiz_db().get_random_flag_quiz(rng).clone()),
+            };
+        }
+        FLAG_CHOICE_CATEGORY => return QuizType::FlagChoice(flag_quiz_db().get_random_flag_choice_quiz(rng)),
+        _ => {}
+    }
+
+    if category == MATH_CATEGORY {
+        return QuizType::Math(generate_math_quiz(rng, difficulty.unwrap_or_default()));
+    }
+
+    if let Some(quiz) = image_quiz_db().get_random_quiz_by_set(rng, category) {
+        return QuizType::Image(quiz.clone());
+    }
+
+    if let Some(sub_category) = chosung_sub_category(category) {
+        if let Some(quiz) = get_random_chosung_quiz(rng, sub_category) {
+            return QuizType::Chosung(quiz);
+        }
+        warn!("{:<12} - no chosung quiz candidates for {:?}", "GAME_DB", sub_category);
+    }
+
+    // 없는 카테고리면 랜덤하게
+    match quiz_db().get_random_quiz_by_category(rng, category, difficulty) {
+        Some(quiz) => QuizType::Simple(quiz.clone()),
+        None => QuizType::Simple(quiz_db().get_any_random_quiz(rng, difficulty).clone()),
+    }
+}
+
+/// 텍스트/국기 퀴즈의 정답 중 초성 문제로 낼 수 있는 것을 골라 만든다.
+/// `category`가 없으면 전체 대상
+pub fn get_random_chosung_quiz<R: Rng>(rng: &mut R, category: Option<&str>) -> Option<ChosungQuiz> {
+    let mut candidates: Vec<(&str, &str)> = Vec::new();
+
+    if category.is_none() || category == Some(FLAG_CATEGORY) {
+        candidates.extend(
+            flag_quiz_db().quizzes.iter()
+                .map(|quiz| (FLAG_CATEGORY, quiz.answer.as_str()))
+        );
+    }
+    if category != Some(FLAG_CATEGORY) {
+        candidates.extend(
+            quiz_db().sorted_categories()
+                .filter(|(c, _)| category.is_none() || category == Some(*c))
+                .flat_map(|(c, quizzes)| quizzes.iter().map(move |quiz| (c, quiz.answer.as_str())))
+        );
+    }
+    candidates.retain(|(_, answer)| ChosungQuiz::is_eligible_answer(answer));
+
+    if candidates.is_empty() {
+        return None;
+    }
+
+    let index = rng.gen_range(0..candidates.len());
+    let (category, answer) = candidates[index];
+    Some(ChosungQuiz::new(category.to_string(), answer.to_string()))
+}
+
 pub struct QuizDB {
     quizzes: HashMap<String, Vec<Quiz>>,
 }
 
 impl QuizDB {
-    pub fn get_any_random_quiz(&self) -> &Quiz {
-        let keys: Vec<&String> = self.quizzes.keys().collect();
-        let mut rng = rand::thread_rng();
-
-        let key_index = rng.gen_range(0..keys.len());
-        let random_key = keys[key_index];
+    /// 카테고리별 문제 수가 달라도 고르게 나오도록 카테고리부터 고른다
+    pub fn get_any_random_quiz<R: Rng>(&self, rng: &mut R, difficulty: Option<Difficulty>) -> &Quiz {
+        let mut categories: Vec<&Vec<Quiz>> = self.sorted_categories()
+            .map(|(_, quizzes)| quizzes)
+            .filter(|quizzes| difficulty.is_none() || quizzes.iter().any(|quiz| Some(quiz.difficulty()) == difficulty))
+            .collect();
+        if categories.is_empty() {
+            categories = self.sorted_categories().map(|(_, quizzes)| quizzes).collect();
+        }
 
-        let quiz_vec = self.quizzes.get(random_key).unwrap();
+        let quizzes: Vec<&Quiz> = categories.choose(rng).unwrap().iter().collect();
+        pick_random_quiz(rng, &quizzes, difficulty).unwrap()
+    }
 
-        let quiz_index = rng.gen_range(0..quiz_vec.len());
+    pub fn get_random_quiz_by_category<R: Rng>(&self, rng: &mut R, category: &str, difficulty: Option<Difficulty>) -> Option<&Quiz> {
+        let quizzes: Vec<&Quiz> = self.quizzes.get(category)?.iter().collect();
+        pick_random_quiz(rng, &quizzes, difficulty)
+    }
 
-        &quiz_vec[quiz_index]
+    /// HashMap 순서는 실행마다 달라서, seed로 고를 때는 이름순으로
+    fn sorted_categories(&self) -> impl Iterator<Item = (&str, &Vec<Quiz>)> {
+        let mut categories: Vec<(&str, &Vec<Quiz>)> = self.quizzes.iter()
+            .map(|(category, quizzes)| (category.as_str(), quizzes))
+            .collect();
+        categories.sort_by_key(|(category, _)| *category);
+        categories.into_iter()
     }
+}
 
-    pub fn get_random_quiz_by_category(&self, category: &String) -> Option<&Quiz> {
-        let quizzes_by_category = self.quizzes.get(category)?;
-        let index = rand::random::<usize>() % quizzes_by_category.len();
-        Some(&quizzes_by_category[index])
+/// 난이도가 맞는 문제 중에서 고르고, 하나도 없으면 전체에서 고른다
+fn pick_random_quiz<'a, R: Rng>(rng: &mut R, quizzes: &[&'a Quiz], difficulty: Option<Difficulty>) -> Option<&'a Quiz> {
+    if let Some(difficulty) = difficulty {
+        let filtered: Vec<&Quiz> = quizzes.iter()
+            .filter(|quiz| quiz.difficulty() == difficulty)
+            .copied()
+            .collect();
+        if let Some(quiz) = filtered.choose(rng) {
+            return Some(quiz);
+        }
     }
+
+    quizzes.choose(rng).copied()
 }
 
 pub fn quiz_db() -> &'static QuizDB {
@@ -71,7 +224,7 @@ pub fn quiz_db() -> &'static QuizDB {
             // quizzes.push(record);
             let category = record.category.clone();
 
-            let quiz_vec = quizzes.entry(category).or_insert(Vec::new());
+            let quiz_vec = quizzes.entry(category).or_default();
             quiz_vec.push(record);
         }
 
@@ -83,9 +236,13 @@ pub fn quiz_db() -> &'static QuizDB {
 
 // const FLAG_QUIZ_DB_DIR: &str = "data";
 const FLAG_QUIZ_DB_DIR: &str = QUIZ_DB_DIR;
+// columns: country_code_alpha_2, country_name, capital(optional), continent(optional)
 const FLAG_QUIZ_DB_FILE: &str = "flags.csv";
-pub const FLAG_IMAGE_CDN_PATH: &str = "https://kakao-quizbot-cdn.joe-brothers.com/flags_640";  // TODO: move to runtime env
+pub const FLAG_IMAGE_CDN_PATH: &str = "https://kakao-quizbot-cdn.joe-brothers.com/flags_640";  // PUBLIC_BASE_URL 없을 때만
 pub const FLAG_IMAGE_EXT: &str = "png";
+// STATIC_FILES_DIR 아래 경로
+pub const FLAG_IMAGE_STATIC_DIR: &str = "flags";
+pub const IMAGE_QUIZ_STATIC_DIR: &str = "images";
 
 // 복붙
 // TODO; quizdb도 trait으로 묶기 ㅠ
@@ -94,11 +251,52 @@ pub struct FlagQuizDB {
 }
 
 impl FlagQuizDB {
-    pub fn get_random_flag_quiz(&self) -> &FlagQuiz {
-        let mut rng = rand::thread_rng();
+    pub fn get_random_flag_quiz<R: Rng>(&self, rng: &mut R) -> &FlagQuiz {
         let index = rng.gen_range(0..self.quizzes.len());
         &self.quizzes[index]
     }
+
+    /// 수도 컬럼이 있는 나라 중에서. 하나도 없으면 None
+    pub fn get_random_capital_quiz<R: Rng>(&self, rng: &mut R, reverse: bool) -> Option<CapitalQuiz> {
+        let candidates: Vec<&FlagQuiz> = self.quizzes.iter()
+            .filter(|quiz| quiz.capital.is_some())
+            .collect();
+        let quiz = candidates.choose(rng)?;
+
+        Some(CapitalQuiz {
+            country_code_alpha_2: quiz.country_code_alpha_2.clone(),
+            country_name: quiz.answer.clone(),
+            capital: quiz.capital.clone().unwrap(),
+            reverse,
+        })
+    }
+
+    /// 보기는 가능하면 같은 대륙의 나라로 채운다
+    pub fn get_random_flag_choice_quiz<R: Rng>(&self, rng: &mut R) -> FlagChoiceQuiz {
+        let answer = self.get_random_flag_quiz(rng);
+
+        let others: Vec<&FlagQuiz> = self.quizzes.iter()
+            .filter(|quiz| quiz.country_code_alpha_2 != answer.country_code_alpha_2)
+            .collect();
+        let (mut same_continent, mut rest): (Vec<&FlagQuiz>, Vec<&FlagQuiz>) = others.into_iter()
+            .partition(|quiz| answer.continent.is_some() && quiz.continent == answer.continent);
+        same_continent.shuffle(rng);
+        rest.shuffle(rng);
+
+        let mut choices: Vec<String> = same_continent.into_iter()
+            .chain(rest)
+            .take(FLAG_CHOICE_COUNT - 1)
+            .map(|quiz| quiz.country_code_alpha_2.clone())
+            .collect();
+        let answer_index = rng.gen_range(0..=choices.len());
+        choices.insert(answer_index, answer.country_code_alpha_2.clone());
+
+        FlagChoiceQuiz {
+            country_name: answer.answer.clone(),
+            choices,
+            answer_index,
+        }
+    }
 }
 
 pub fn flag_quiz_db() -> &'static FlagQuizDB {
@@ -112,6 +310,7 @@ pub fn flag_quiz_db() -> &'static FlagQuizDB {
             .expect("flag quiz db file not found");
 
         let mut quizzes: Vec<FlagQuiz> = Vec::new();
+        let mut country_codes: HashSet<String> = HashSet::new();
 
         for result in reader.deserialize() {
             let record: FlagQuiz = match result {
@@ -121,6 +320,14 @@ pub fn flag_quiz_db() -> &'static FlagQuizDB {
                     continue;
                 }
             };
+            if let Err(reason) = record.validate() {
+                warn!("{:<12} - FlagQuiz invalid: {}", "GAME_DB", reason);
+                continue;
+            }
+            if !country_codes.insert(record.country_code_alpha_2.clone()) {
+                warn!("{:<12} - FlagQuiz duplicated: {}", "GAME_DB", record.country_code_alpha_2);
+                continue;
+            }
             quizzes.push(record);
         }
 
@@ -130,9 +337,136 @@ pub fn flag_quiz_db() -> &'static FlagQuizDB {
     })
 }
 
+const IMAGE_QUIZ_DB_DIR: &str = QUIZ_DB_DIR;
+// columns: set, image, alt, answer, question(optional), aspect_ratio(optional)
+const IMAGE_QUIZ_DB_FILE: &str = "images.csv";
+
+// 세트 이름 -> 문제들. 세트 이름이 곧 카테고리
+pub struct ImageQuizDB {
+    quizzes: HashMap<String, Vec<ImageQuiz>>,
+}
+
+impl ImageQuizDB {
+    pub fn has_set(&self, set: &str) -> bool {
+        self.quizzes.contains_key(set)
+    }
+
+    pub fn get_random_quiz_by_set<R: Rng>(&self, rng: &mut R, set: &str) -> Option<&ImageQuiz> {
+        self.quizzes.get(set)?.choose(rng)
+    }
+}
+
+/// 이미지 퀴즈는 선택 사항이라 파일이 없으면 빈 db
+pub fn image_quiz_db() -> &'static ImageQuizDB {
+    static INSTANCE: OnceLock<ImageQuizDB> = OnceLock::new();
+
+    INSTANCE.get_or_init(|| {
+        let current_dir = std::env::current_dir().unwrap();
+        let image_quiz_db_file = current_dir.join(IMAGE_QUIZ_DB_DIR).join(IMAGE_QUIZ_DB_FILE);
+
+        let mut quizzes: HashMap<String, Vec<ImageQuiz>> = HashMap::new();
+
+        let mut reader = match csv::Reader::from_path(&image_quiz_db_file) {
+            Ok(reader) => reader,
+            Err(e) => {
+                warn!("{:<12} - image quiz db not loaded ({:?}): {}", "GAME_DB", image_quiz_db_file, e);
+                return ImageQuizDB { quizzes };
+            }
+        };
+
+        for result in reader.deserialize() {
+            let record: ImageQuiz = match result {
+                Ok(record) => record,
+                Err(e) => {
+                    warn!("{:<12} - ImageQuiz load failed: {}", "GAME_DB", e);
+                    continue;
+                }
+            };
+            if let Err(reason) = record.validate() {
+                warn!("{:<12} - ImageQuiz invalid: {}", "GAME_DB", reason);
+                continue;
+            }
+            // 기존 카테고리와 겹치면 구분이 안된다
+            if QUIZ_CATEGORIES.contains(record.set.as_str()) || is_flag_db_category(&record.set) {
+                warn!("{:<12} - ImageQuiz set name conflicts with category: {}", "GAME_DB", record.set);
+                continue;
+            }
+
+            quizzes.entry(record.set.clone()).or_default().push(record);
+        }
+
+        ImageQuizDB { quizzes }
+    })
+}
+
 // TODO: 더 깔끔하게
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum QuizType {
     Simple(Quiz),
     Flag(FlagQuiz),
+    Chosung(ChosungQuiz),
+    Capital(CapitalQuiz),
+    FlagChoice(FlagChoiceQuiz),
+    Image(ImageQuiz),
+    Math(MathQuiz),
+}
+
+impl QuizType {
+    pub fn as_question(&self) -> &dyn Question {
+        match self {
+            QuizType::Simple(quiz) => quiz,
+            QuizType::Flag(quiz) => quiz,
+            QuizType::Chosung(quiz) => quiz,
+            QuizType::Capital(quiz) => quiz,
+            QuizType::FlagChoice(quiz) => quiz,
+            QuizType::Image(quiz) => quiz,
+            QuizType::Math(quiz) => quiz,
+        }
+    }
+
+    pub fn is_correct_answer(&self, answer: &str) -> bool {
+        self.as_question().is_correct_answer(answer)
+    }
+
+    /// `level`번째 힌트. 한 번에 한 글자씩 더 보여주고 마지막 글자는 보여주지 않는다.
+    /// 보기 중에 고르는 문제는 힌트가 없다
+    pub fn hint(&self, level: usize) -> Option<String> {
+        let answer = match self {
+            QuizType::FlagChoice(_) => return None,
+            quiz => quiz.as_question().reveal_answer(),
+        };
+        let length = answer.chars().filter(|c| !c.is_whitespace()).count();
+        // 초성 문제는 이미 초성을 보여주고 있으니 첫 힌트부터 글자를 보여준다
+        let revealed = match self {
+            QuizType::Chosung(_) => level,
+            _ => level - 1,
+        };
+        Some(hangul::hint(&answer, revealed.min(length.saturating_sub(1))))
+    }
+
+    /// 문제와 같이 보여줘야 하는 이미지. 국기 고르기는 보기마다 하나씩
+    pub fn image_urls(&self) -> Vec<String> {
+        match self {
+            QuizType::Flag(quiz) => vec![quiz.image_url()],
+            // 수도 -> 나라는 국기가 곧 정답
+            QuizType::Capital(quiz) if !quiz.reverse => vec![quiz.image_url()],
+            QuizType::FlagChoice(quiz) => quiz.choice_image_urls(),
+            QuizType::Image(quiz) => quiz.image_url().into_iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// 사용자별 기록에 쓰는 카테고리 이름. 초성은 세부 카테고리 없이 "초성"
+    pub fn category(&self) -> &str {
+        match self {
+            QuizType::Simple(quiz) => &quiz.category,
+            QuizType::Flag(_) => FLAG_CATEGORY,
+            QuizType::Chosung(_) => CHOSUNG_CATEGORY,
+            QuizType::Capital(quiz) if quiz.reverse => CAPITAL_REVERSE_CATEGORY,
+            QuizType::Capital(_) => CAPITAL_CATEGORY,
+            QuizType::FlagChoice(_) => FLAG_CHOICE_CATEGORY,
+            QuizType::Image(quiz) => &quiz.set,
+            QuizType::Math(_) => MATH_CATEGORY,
+        }
+    }
 }
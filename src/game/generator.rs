@@ -0,0 +1,269 @@
+//! 파일 없이 그때그때 만들어내는 문제들 (수학)
+//!
+//! 난이도별로 범위만 다르게 해서 무한히 생성한다.
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::game::model::{Difficulty, Question};
+use crate::game::numeral::parse_number;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MathQuizKind {
+    Arithmetic,
+    UnitConversion,
+    Sequence,
+}
+
+impl MathQuizKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MathQuizKind::Arithmetic => "암산",
+            MathQuizKind::UnitConversion => "단위 변환",
+            MathQuizKind::Sequence => "수열",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MathQuiz {
+    pub kind: MathQuizKind,
+    pub difficulty: Difficulty,
+    pub question: String,
+    pub answer: i64,
+    // 정답 뒤에 붙여도 되는 단위. ex) "m"
+    pub unit: Option<String>,
+}
+
+impl Question for MathQuiz {
+    fn info_before(&self, round_header: &str) -> String {
+        format!(
+            "{} (수학 - {}, {})\n{}",
+            round_header, self.kind.name(), self.difficulty.name(), self.question,
+        )
+    }
+
+    /// "1000", "1,000", "천", "1000m" 모두 허용
+    fn is_correct_answer(&self, answer: &str) -> bool {
+        let answer = answer.trim();
+        let answer = self.unit.as_deref()
+            .and_then(|unit| answer.strip_suffix(unit))
+            .unwrap_or(answer);
+
+        parse_number(answer) == Some(self.answer)
+    }
+
+    /// 생성형이라 문제별로는 의미가 없어서 유형+난이도 단위로 묶는다
+    fn question_id(&self) -> String {
+        format!("math:{:?}:{:?}", self.kind, self.difficulty)
+    }
+
+    fn reveal_answer(&self) -> String {
+        match &self.unit {
+            Some(unit) => format!("{}{}", self.answer, unit),
+            None => self.answer.to_string(),
+        }
+    }
+}
+
+pub fn generate_math_quiz<R: Rng>(rng: &mut R, difficulty: Difficulty) -> MathQuiz {
+    match rng.gen_range(0..3) {
+        0 => arithmetic(rng, difficulty),
+        1 => unit_conversion(rng, difficulty),
+        _ => sequence(rng, difficulty),
+    }
+}
+
+fn arithmetic<R: Rng>(rng: &mut R, difficulty: Difficulty) -> MathQuiz {
+    let (expression, answer) = match difficulty {
+        Difficulty::Easy => {
+            let a = rng.gen_range(1..=50);
+            let b = rng.gen_range(1..=50);
+            if rng.gen_bool(0.5) {
+                (format!("{} + {}", a, b), a + b)
+            } else {
+                let (a, b) = (a.max(b), a.min(b));
+                (format!("{} - {}", a, b), a - b)
+            }
+        }
+        Difficulty::Normal => match rng.gen_range(0..4) {
+            0 => {
+                let a = rng.gen_range(10..=999);
+                let b = rng.gen_range(10..=999);
+                (format!("{} + {}", a, b), a + b)
+            }
+            1 => {
+                let a = rng.gen_range(10..=999);
+                let b = rng.gen_range(10..=999);
+                let (a, b) = (a.max(b), a.min(b));
+                (format!("{} - {}", a, b), a - b)
+            }
+            2 => {
+                let a = rng.gen_range(2..=19);
+                let b = rng.gen_range(2..=19);
+                (format!("{} × {}", a, b), a * b)
+            }
+            _ => {
+                // 나누어 떨어지도록 몫부터 정한다
+                let divisor = rng.gen_range(2..=12);
+                let quotient = rng.gen_range(2..=30);
+                (format!("{} ÷ {}", divisor * quotient, divisor), quotient)
+            }
+        },
+        Difficulty::Hard => {
+            let a = rng.gen_range(2..=30);
+            let b = rng.gen_range(2..=30);
+            let c = rng.gen_range(10..=500);
+            match rng.gen_range(0..4) {
+                0 => (format!("{} × {} + {}", a, b, c), a * b + c),
+                1 => (format!("{} + {} × {}", c, a, b), c + a * b),
+                2 => (format!("{} × {} - {}", a, b, c), a * b - c),
+                _ => {
+                    let a = rng.gen_range(11..=99);
+                    let b = rng.gen_range(11..=99);
+                    (format!("{} × {}", a, b), a * b)
+                }
+            }
+        }
+    };
+
+    MathQuiz {
+        kind: MathQuizKind::Arithmetic,
+        difficulty,
+        question: format!("{} = ?", expression),
+        answer,
+        unit: None,
+    }
+}
+
+// (큰 단위, 작은 단위, 배수)
+const UNITS: [(&str, &str, i64); 7] = [
+    ("km", "m", 1_000),
+    ("m", "cm", 100),
+    ("kg", "g", 1_000),
+    ("L", "mL", 1_000),
+    ("시간", "분", 60),
+    ("분", "초", 60),
+    ("일", "시간", 24),
+];
+
+// 어려움에서만
+const HARD_UNITS: [(&str, &str, i64); 3] = [
+    ("시간", "초", 3_600),
+    ("일", "분", 1_440),
+    ("km", "cm", 100_000),
+];
+
+fn unit_conversion<R: Rng>(rng: &mut R, difficulty: Difficulty) -> MathQuiz {
+    let (big, small, factor) = match difficulty {
+        Difficulty::Hard if rng.gen_bool(0.5) => *HARD_UNITS.choose(rng).unwrap(),
+        _ => *UNITS.choose(rng).unwrap(),
+    };
+    let value = match difficulty {
+        Difficulty::Easy => rng.gen_range(1..=9),
+        Difficulty::Normal => rng.gen_range(1..=50),
+        Difficulty::Hard => rng.gen_range(2..=99),
+    };
+
+    // 쉬움은 큰 단위 -> 작은 단위만
+    let to_small = difficulty == Difficulty::Easy || rng.gen_bool(0.5);
+    let (question, answer, unit) = if to_small {
+        (format!("{}{} = ? {}", value, big, small), value * factor, small)
+    } else {
+        (format!("{}{} = ? {}", value * factor, small, big), value, big)
+    };
+
+    MathQuiz {
+        kind: MathQuizKind::UnitConversion,
+        difficulty,
+        question,
+        answer,
+        unit: Some(unit.to_string()),
+    }
+}
+
+// 보여주는 항 개수
+const SEQUENCE_LENGTH: usize = 5;
+
+fn sequence<R: Rng>(rng: &mut R, difficulty: Difficulty) -> MathQuiz {
+    let terms: Vec<i64> = match difficulty {
+        Difficulty::Easy => {
+            let start = rng.gen_range(1..=20);
+            let diff = rng.gen_range(1..=9);
+            (0..=SEQUENCE_LENGTH as i64).map(|i| start + diff * i).collect()
+        }
+        Difficulty::Normal => {
+            if rng.gen_bool(0.5) {
+                let start = rng.gen_range(-20..=50);
+                let diff = *[-9, -7, -5, -3, 3, 5, 7, 9, 11, 13].choose(rng).unwrap();
+                (0..=SEQUENCE_LENGTH as i64).map(|i| start + diff * i).collect()
+            } else {
+                let start: i64 = rng.gen_range(1..=5);
+                let ratio: i64 = rng.gen_range(2..=3);
+                (0..=SEQUENCE_LENGTH as u32).map(|i| start * ratio.pow(i)).collect()
+            }
+        }
+        Difficulty::Hard => match rng.gen_range(0..3) {
+            // 제곱수 + c
+            0 => {
+                let offset = rng.gen_range(1..=5);
+                let c = rng.gen_range(-10..=10);
+                (0..=SEQUENCE_LENGTH as i64).map(|i| (i + offset).pow(2) + c).collect()
+            }
+            // 피보나치 꼴
+            1 => {
+                let mut terms = vec![rng.gen_range(1..=5), rng.gen_range(1..=9)];
+                while terms.len() <= SEQUENCE_LENGTH {
+                    let next = terms[terms.len() - 1] + terms[terms.len() - 2];
+                    terms.push(next);
+                }
+                terms
+            }
+            // 계차가 등차수열
+            _ => {
+                let mut term = rng.gen_range(1..=20);
+                let mut diff = rng.gen_range(1..=5);
+                let step = rng.gen_range(1..=4);
+                let mut terms = Vec::with_capacity(SEQUENCE_LENGTH + 1);
+                for _ in 0..=SEQUENCE_LENGTH {
+                    terms.push(term);
+                    term += diff;
+                    diff += step;
+                }
+                terms
+            }
+        },
+    };
+
+    let shown: Vec<String> = terms[..SEQUENCE_LENGTH].iter().map(|n| n.to_string()).collect();
+
+    MathQuiz {
+        kind: MathQuizKind::Sequence,
+        difficulty,
+        question: format!("{}, ?", shown.join(", ")),
+        answer: terms[SEQUENCE_LENGTH],
+        unit: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn generated_answers_parse_back() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+            for _ in 0..1_000 {
+                let quiz = generate_math_quiz(&mut rng, difficulty);
+                assert!(quiz.is_correct_answer(&quiz.answer.to_string()), "{:?}", quiz);
+                assert!(quiz.is_correct_answer(&quiz.reveal_answer()), "{:?}", quiz);
+                assert!(!quiz.is_correct_answer(&(quiz.answer + 1).to_string()), "{:?}", quiz);
+            }
+        }
+    }
+}
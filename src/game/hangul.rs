@@ -0,0 +1,133 @@
+//! 한글 음절 분해 유틸
+//!
+//! 유니코드 한글 음절(가~힣)은 `0xAC00 + (초성 * 21 + 중성) * 28 + 종성` 으로 조합되어 있다.
+//! 초성 퀴즈, 힌트, 정답 정규화 등에서 공통으로 쓴다.
+
+const SYLLABLE_BASE: u32 = 0xAC00;
+const SYLLABLE_LAST: u32 = 0xD7A3;
+const JUNGSUNG_COUNT: u32 = 21;
+const JONGSUNG_COUNT: u32 = 28;
+
+// 호환용 자모 (ㄱ, ㅏ 등 키보드로 입력되는 글자)
+const CHOSUNG: [char; 19] = [
+    'ㄱ', 'ㄲ', 'ㄴ', 'ㄷ', 'ㄸ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅃ', 'ㅅ',
+    'ㅆ', 'ㅇ', 'ㅈ', 'ㅉ', 'ㅊ', 'ㅋ', 'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+const JUNGSUNG: [char; 21] = [
+    'ㅏ', 'ㅐ', 'ㅑ', 'ㅒ', 'ㅓ', 'ㅔ', 'ㅕ', 'ㅖ', 'ㅗ', 'ㅘ',
+    'ㅙ', 'ㅚ', 'ㅛ', 'ㅜ', 'ㅝ', 'ㅞ', 'ㅟ', 'ㅠ', 'ㅡ', 'ㅢ',
+    'ㅣ',
+];
+
+// 0번은 받침 없음
+const JONGSUNG: [Option<char>; 28] = [
+    None, Some('ㄱ'), Some('ㄲ'), Some('ㄳ'), Some('ㄴ'), Some('ㄵ'), Some('ㄶ'), Some('ㄷ'),
+    Some('ㄹ'), Some('ㄺ'), Some('ㄻ'), Some('ㄼ'), Some('ㄽ'), Some('ㄾ'), Some('ㄿ'), Some('ㅀ'),
+    Some('ㅁ'), Some('ㅂ'), Some('ㅄ'), Some('ㅅ'), Some('ㅆ'), Some('ㅇ'), Some('ㅈ'), Some('ㅊ'),
+    Some('ㅋ'), Some('ㅌ'), Some('ㅍ'), Some('ㅎ'),
+];
+
+/// 분해된 한 음절. ex) '각' -> (ㄱ, ㅏ, Some(ㄱ))
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Syllable {
+    pub chosung: char,
+    pub jungsung: char,
+    pub jongsung: Option<char>,
+}
+
+pub fn is_hangul_syllable(c: char) -> bool {
+    (SYLLABLE_BASE..=SYLLABLE_LAST).contains(&(c as u32))
+}
+
+/// 완성형 한글 음절이 아니면 None
+pub fn decompose(c: char) -> Option<Syllable> {
+    if !is_hangul_syllable(c) {
+        return None;
+    }
+
+    let offset = c as u32 - SYLLABLE_BASE;
+    let cho = offset / (JUNGSUNG_COUNT * JONGSUNG_COUNT);
+    let jung = (offset / JONGSUNG_COUNT) % JUNGSUNG_COUNT;
+    let jong = offset % JONGSUNG_COUNT;
+
+    Some(Syllable {
+        chosung: CHOSUNG[cho as usize],
+        jungsung: JUNGSUNG[jung as usize],
+        jongsung: JONGSUNG[jong as usize],
+    })
+}
+
+pub fn chosung(c: char) -> Option<char> {
+    decompose(c).map(|s| s.chosung)
+}
+
+/// 한글 음절은 초성으로 바꾸고, 나머지 글자(공백, 숫자 등)는 그대로 둔다.
+/// ex) "대한민국" -> "ㄷㅎㅁㄱ"
+pub fn to_chosung(s: &str) -> String {
+    s.chars()
+        .map(|c| chosung(c).unwrap_or(c))
+        .collect()
+}
+
+/// 공백을 제외한 모든 글자가 완성형 한글인지
+pub fn is_all_hangul(s: &str) -> bool {
+    let mut chars = s.chars().filter(|c| !c.is_whitespace()).peekable();
+    chars.peek().is_some() && chars.all(is_hangul_syllable)
+}
+
+/// 정답 비교용. 공백을 모두 빼고 소문자로 바꾼다
+/// ex) "뉴 질랜드" -> "뉴질랜드"
+pub fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 띄어쓰기와 대소문자는 달라도 같은 답으로 본다
+pub fn is_same_answer(expected: &str, answer: &str) -> bool {
+    normalize(expected) == normalize(answer)
+}
+
+// 힌트에서 가린 글자 (한글이 아닌 글자)
+const HIDDEN_CHAR: char = '○';
+
+/// 앞에서 `revealed`글자만 보여주고, 나머지 한글은 초성으로, 다른 글자는 ○로 가린다. 공백은 그대로.
+/// ex) ("대한민국", 1) -> "대ㅎㅁㄱ"
+pub fn hint(answer: &str, revealed: usize) -> String {
+    let mut shown = 0;
+    answer.chars()
+        .map(|c| {
+            if c.is_whitespace() {
+                return c;
+            }
+            shown += 1;
+            if shown <= revealed {
+                c
+            } else {
+                chosung(c).unwrap_or(HIDDEN_CHAR)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hint_reveals_from_the_front() {
+        assert_eq!(hint("대한민국", 0), "ㄷㅎㅁㄱ");
+        assert_eq!(hint("대한민국", 1), "대ㅎㅁㄱ");
+        assert_eq!(hint("뉴 질랜드", 2), "뉴 질ㄹㄷ");
+        assert_eq!(hint("G20", 1), "G○○");
+    }
+
+    #[test]
+    fn normalize_ignores_spaces_and_case() {
+        assert_eq!(normalize(" 뉴 질랜드 "), "뉴질랜드");
+        assert_eq!(normalize("G20"), normalize("g 20"));
+        assert_eq!(to_chosung("뉴 질랜드"), "ㄴ ㅈㄹㄷ");
+    }
+}
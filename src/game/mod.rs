@@ -1,4 +1,16 @@
 pub mod db;
 pub mod state;
 pub mod model;
-mod temp_inmemory_db;
+pub mod hangul;
+pub mod numeral;
+pub mod generator;
+pub mod stats;
+pub mod mode;
+pub mod team;
+pub mod challenge;
+pub mod daily;
+pub mod stopwatch;
+pub mod store;
+pub mod actor;
+pub mod profile;
+pub mod score;
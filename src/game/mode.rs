@@ -0,0 +1,226 @@
+//! 게임 모드: 종료 조건과 끝났을 때 보여줄 요약 메시지
+//!
+//! - 클래식 (`N문제`): 정해진 문제 수만큼, 문제마다 먼저 맞힌 사람이 1점
+//! - 선착 (`N점`): 누군가 N점을 먼저 모으면 끝
+//! - 서바이벌: 틀리면 그 게임에서 탈락, 한 명 남으면 끝
+//! - 블리츠 (`블리츠`, `블리츠N분`): N분 동안 최대한 많이
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::state::MAX_ROUNDS;
+use crate::game::team::TeamSummary;
+use crate::skill::Mentions;
+
+const MAX_ROUNDS_LIMIT: u8 = 30;
+const MAX_TARGET_SCORE: u32 = 20;
+const MAX_BLITZ_MINUTES: u64 = 10;
+const DEFAULT_BLITZ_DURATION: Duration = Duration::from_secs(3 * 60);
+// 서바이벌이 끝나지 않는 경우 대비
+pub const SURVIVAL_MAX_ROUNDS: u8 = 30;
+
+// 요약 메시지에 보여줄 순위 수
+const SUMMARY_RANKING_SIZE: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMode {
+    Rounds(u8),
+    FirstTo(u32),
+    Survival,
+    Blitz(Duration),
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        GameMode::Rounds(MAX_ROUNDS)
+    }
+}
+
+impl GameMode {
+    /// "클래식", "10문제", "5점", "서바이벌", "블리츠", "블리츠5분"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "클래식" => return Some(GameMode::default()),
+            "서바이벌" => return Some(GameMode::Survival),
+            "블리츠" => return Some(GameMode::Blitz(DEFAULT_BLITZ_DURATION)),
+            _ => {}
+        }
+
+        if let Some(rounds) = name.strip_suffix("문제").and_then(|n| n.parse::<u8>().ok()) {
+            return (1..=MAX_ROUNDS_LIMIT).contains(&rounds).then_some(GameMode::Rounds(rounds));
+        }
+        if let Some(target) = name.strip_suffix('점').and_then(|n| n.parse::<u32>().ok()) {
+            return (1..=MAX_TARGET_SCORE).contains(&target).then_some(GameMode::FirstTo(target));
+        }
+        if let Some(minutes) = name.strip_prefix("블리츠")
+            .and_then(|n| n.strip_suffix('분'))
+            .and_then(|n| n.parse::<u64>().ok()) {
+            return (1..=MAX_BLITZ_MINUTES).contains(&minutes)
+                .then_some(GameMode::Blitz(Duration::from_secs(minutes * 60)));
+        }
+
+        None
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            GameMode::Rounds(rounds) => format!("클래식 {}문제", rounds),
+            GameMode::FirstTo(target) => format!("{}점 선착", target),
+            GameMode::Survival => "서바이벌".to_string(),
+            GameMode::Blitz(duration) => format!("블리츠 {}분", duration.as_secs() / 60),
+        }
+    }
+
+    /// 문제 앞에 붙는 머리말. ex) "[2/3]", "[2번째 · 남은 시간 1:23]"
+    pub fn round_header(&self, current_round: u8, elapsed: Duration) -> String {
+        match self {
+            GameMode::Rounds(rounds) => format!("[{}/{}]", current_round, rounds),
+            GameMode::FirstTo(target) => format!("[{}번째 · {}점 선착]", current_round, target),
+            GameMode::Survival => format!("[{}번째 · 서바이벌]", current_round),
+            GameMode::Blitz(duration) => {
+                let remaining = duration.saturating_sub(elapsed).as_secs();
+                format!("[{}번째 · 남은 시간 {}:{:02}]", current_round, remaining / 60, remaining % 60)
+            }
+        }
+    }
+}
+
+/// 게임이 끝났을 때의 결과
+#[derive(Debug, Clone)]
+pub struct GameSummary {
+    pub mode: GameMode,
+    pub rounds_played: u8,
+    // (user_id, 이번 게임 점수). 순위 순서 (서바이벌은 생존자가 먼저)
+    pub rankings: Vec<(String, u32)>,
+    // 서바이벌 생존자
+    pub survivors: Vec<String>,
+    // 팀전일 때만
+    pub teams: Option<TeamSummary>,
+    // 도전일 때만
+    pub challenge_code: Option<String>,
+}
+
+impl GameSummary {
+    /// 이긴 사람, 순위 등은 `mentions`에 멘션으로 넣는다
+    pub fn message(&self, mentions: &mut Mentions) -> String {
+        let headline = match self.mode {
+            GameMode::Rounds(rounds) => format!("✅ {}문제 다 풀었습니다 :)", rounds),
+            GameMode::FirstTo(target) => match self.rankings.first() {
+                Some((user_id, score)) if *score >= target => format!("🏁 {} 님이 먼저 {}점 달성!", mentions.placeholder(user_id), target),
+                _ => format!("🏁 {}점 선착 게임이 끝났습니다", target),
+            },
+            GameMode::Survival => match self.survivors.as_slice() {
+                [] => "🧟 모두 탈락했어요!".to_string(),
+                [survivor] => format!("🧟 최후의 생존자: {}", mentions.placeholder(survivor)),
+                survivors => format!("🧟 서바이벌 종료! 생존자 {}명", survivors.len()),
+            },
+            GameMode::Blitz(_) => format!("⏱️ 시간 종료! 모두 {}문제를 풀었어요", self.rounds_played),
+        };
+
+        let mut message = match self.standings(mentions) {
+            Some(standings) => format!("{}\n{}", headline, standings),
+            None => headline,
+        };
+        if let Some(teams) = &self.teams {
+            message.push_str(format!("\n{}", teams.message(mentions)).as_str());
+        }
+        if let Some(code) = &self.challenge_code {
+            message.push_str(format!("\n⚔️ 도전 {}: 상대 방도 끝나면 결과를 알려드릴게요", code).as_str());
+        }
+        message
+    }
+
+    /// 1등. 서바이벌은 혼자 살아남은 사람, 나머지는 점수가 있는 맨 위 (동점이면 순위표 순서)
+    pub fn winner(&self) -> Option<&str> {
+        match self.mode {
+            GameMode::Survival => match self.survivors.as_slice() {
+                [survivor] => Some(survivor.as_str()),
+                _ => None,
+            },
+            _ => self.rankings.first()
+                .filter(|(_, score)| *score > 0)
+                .map(|(user_id, _)| user_id.as_str()),
+        }
+    }
+
+    /// 순위 부분만. 점수를 얻은 사람이 없으면 None
+    pub fn standings(&self, mentions: &mut Mentions) -> Option<String> {
+        if self.rankings.is_empty() {
+            return None;
+        }
+
+        let ranking_lines: Vec<String> = self.rankings.iter()
+            .take(SUMMARY_RANKING_SIZE)
+            .enumerate()
+            .map(|(i, (user_id, score))| format!("{}. {} {}점", i + 1, mentions.placeholder(user_id), score))
+            .collect();
+
+        Some(format!("🏆 순위\n{}", ranking_lines.join("\n")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_mode_names() {
+        let minutes = |minutes: u64| GameMode::Blitz(Duration::from_secs(minutes * 60));
+        let cases = [
+            ("클래식", Some(GameMode::Rounds(MAX_ROUNDS))),
+            ("10문제", Some(GameMode::Rounds(10))),
+            ("30문제", Some(GameMode::Rounds(30))),
+            ("0문제", None),
+            ("31문제", None),
+            ("5점", Some(GameMode::FirstTo(5))),
+            ("0점", None),
+            ("21점", None),
+            ("서바이벌", Some(GameMode::Survival)),
+            ("블리츠", Some(minutes(3))),
+            ("블리츠3분", Some(minutes(3))),
+            ("블리츠10분", Some(minutes(10))),
+            ("블리츠0분", None),
+            ("블리츠11분", None),
+            ("삼문제", None),
+            ("모드", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(GameMode::from_name(name), expected, "{:?}", name);
+        }
+    }
+
+    fn summary(mode: GameMode, rankings: &[(&str, u32)], survivors: &[&str]) -> GameSummary {
+        GameSummary {
+            mode,
+            rounds_played: 3,
+            rankings: rankings.iter().map(|(user_id, score)| (user_id.to_string(), *score)).collect(),
+            survivors: survivors.iter().map(|user_id| user_id.to_string()).collect(),
+            teams: None,
+            challenge_code: None,
+        }
+    }
+
+    #[test]
+    fn winner_is_the_top_scorer_or_the_last_survivor() {
+        assert_eq!(summary(GameMode::Rounds(3), &[("alice", 2), ("bob", 1)], &[]).winner(), Some("alice"));
+        assert_eq!(summary(GameMode::Rounds(3), &[("alice", 0)], &[]).winner(), None);
+        assert_eq!(summary(GameMode::FirstTo(2), &[], &[]).winner(), None);
+
+        // 서바이벌은 점수보다 생존
+        assert_eq!(summary(GameMode::Survival, &[("bob", 0), ("alice", 3)], &["bob"]).winner(), Some("bob"));
+        assert_eq!(summary(GameMode::Survival, &[("alice", 1), ("bob", 1)], &["alice", "bob"]).winner(), None);
+        assert_eq!(summary(GameMode::Survival, &[("alice", 1)], &[]).winner(), None);
+    }
+
+    #[test]
+    fn headline_follows_the_end_condition() {
+        let headline = |summary: GameSummary| summary.message(&mut Mentions::default()).lines().next().unwrap().to_string();
+
+        assert_eq!(headline(summary(GameMode::FirstTo(2), &[("alice", 2)], &[])), "🏁 {{#mentions.user1}} 님이 먼저 2점 달성!");
+        // 목표에 닿기 전에 끝났다 (중지 등)
+        assert_eq!(headline(summary(GameMode::FirstTo(2), &[("alice", 1)], &[])), "🏁 2점 선착 게임이 끝났습니다");
+        assert_eq!(headline(summary(GameMode::Survival, &[], &["bob"])), "🧟 최후의 생존자: {{#mentions.user1}}");
+        assert_eq!(headline(summary(GameMode::Survival, &[], &[])), "🧟 모두 탈락했어요!");
+    }
+}
@@ -1,33 +1,141 @@
-use serde::Deserialize;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::game;
-use crate::game::db::{FLAG_IMAGE_CDN_PATH, FLAG_IMAGE_EXT};
+use crate::config::config;
+use crate::game::db::{FLAG_IMAGE_CDN_PATH, FLAG_IMAGE_EXT, FLAG_IMAGE_STATIC_DIR, IMAGE_QUIZ_STATIC_DIR};
+use crate::game::{hangul, stats};
 
-#[derive(Debug, Clone, Deserialize)]
+/// 모든 문제 유형의 공통 인터페이스
+pub trait Question {
+    fn info_before(&self, round_header: &str) -> String;
+    fn is_correct_answer(&self, answer: &str) -> bool;
+
+    /// 통계 집계용 문제 식별자. 같은 문제면 재시작해도 같아야 한다
+    fn question_id(&self) -> String;
+
+    /// 패스 등으로 정답을 알려줄 때
+    fn reveal_answer(&self) -> String;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "쉬움" => Some(Difficulty::Easy),
+            "보통" => Some(Difficulty::Normal),
+            "어려움" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "쉬움",
+            Difficulty::Normal => "보통",
+            Difficulty::Hard => "어려움",
+        }
+    }
+
+    pub fn harder(&self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            _ => Difficulty::Hard,
+        }
+    }
+
+    pub fn easier(&self) -> Self {
+        match self {
+            Difficulty::Hard => Difficulty::Normal,
+            _ => Difficulty::Easy,
+        }
+    }
+}
+
+// csv에는 "쉬움", "보통", "어려움"으로 적는다
+impl<'de> Deserialize<'de> for Difficulty {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Difficulty::from_name(name.trim())
+            .ok_or_else(|| de::Error::custom(format!("unknown difficulty: {}", name)))
+    }
+}
+
+// 게임 저장용. 읽을 때와 같은 이름으로
+impl Serialize for Difficulty {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+/// `시작 {카테고리} {난이도}` 의 난이도 옵션
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DifficultyMode {
+    Fixed(Difficulty),
+    // 방이 얼마나 빨리 맞히는지에 따라 올리고 내린다
+    Adaptive,
+}
+
+impl DifficultyMode {
+    pub const ADAPTIVE_NAME: &'static str = "적응형";
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name == Self::ADAPTIVE_NAME {
+            return Some(DifficultyMode::Adaptive);
+        }
+        Difficulty::from_name(name).map(DifficultyMode::Fixed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Quiz {
     pub category: String,
     question: String,
     pub answer: String,
     pub comment: Option<String>,
+    // 비어있으면 보통
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
 }
 
 impl Quiz {
-    pub fn info_before(&self, current_round: u8) -> String {
-        format!("[{}/{}] ({})\n{}", current_round, game::state::MAX_ROUNDS, self.category, self.question)
+    /// 통계가 충분하면 실제 정답률 기준, 아니면 csv에 적힌 난이도
+    pub fn difficulty(&self) -> Difficulty {
+        stats::empirical_difficulty(&self.question_id())
+            .or(self.difficulty)
+            .unwrap_or_default()
+    }
+}
+
+impl Question for Quiz {
+    fn info_before(&self, round_header: &str) -> String {
+        format!("{} ({})\n{}", round_header, self.category, self.question)
     }
     
-    pub fn is_correct_answer(&self, answer: &str) -> bool {
-        self.answer == answer
+    fn is_correct_answer(&self, answer: &str) -> bool {
+        hangul::is_same_answer(&self.answer, answer)
     }
 
     // pub fn info_after(&self) -> String {
     //     format!("정답: {}\n{}", self.answer, self.comment.as_deref().unwrap_or_default())
     // }
+
+    fn question_id(&self) -> String {
+        format!("quiz:{}", stats::stable_hash(&format!("{}:{}", self.category, self.question)))
+    }
+
+    fn reveal_answer(&self) -> String {
+        self.answer.clone()
+    }
 }
 
-// TODO: 텍스트 퀴즈랑 동일 trait으로 묶기
 // extension은 현재는 전부 png
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlagQuiz {
     // ex) "kr"
     pub country_code_alpha_2: String,
@@ -35,22 +143,333 @@ pub struct FlagQuiz {
     // ex) "대한민국"
     #[serde(rename = "country_name")]
     pub answer: String,
+
+    // ex) "서울". 수도가 없거나 애매한 곳은 비워둔다
+    #[serde(default)]
+    pub capital: Option<String>,
+
+    // ex) "아시아". 국기 고르기 보기를 비슷한 나라로 채울 때 사용
+    #[serde(default)]
+    pub continent: Option<String>,
 }
 
-impl FlagQuiz {
-    pub fn is_correct_answer(&self, answer: &str) -> bool {
-        self.answer == answer
+impl Question for FlagQuiz {
+    fn info_before(&self, round_header: &str) -> String {
+        format!("{} 이 국기는 어느 나라의 국기일까요?", round_header)
     }
-    
-    pub fn info_before(&self, current_round: u8) -> String {
-        format!("[{}/{}] 이 국기는 어느 나라의 국기일까요?", current_round, game::state::MAX_ROUNDS)
+
+    fn is_correct_answer(&self, answer: &str) -> bool {
+        hangul::is_same_answer(&self.answer, answer)
+    }
+
+    fn question_id(&self) -> String {
+        format!("flag:{}", self.country_code_alpha_2)
     }
 
+    fn reveal_answer(&self) -> String {
+        self.answer.clone()
+    }
+}
+
+impl FlagQuiz {
     // pub fn title(&self, current_round: u8) -> String {
     //     format!("[{}/{}]", current_round, game::state::MAX_ROUNDS)
     // }
     
     pub fn image_url(&self) -> String {
-        format!("{}/{}.{}", FLAG_IMAGE_CDN_PATH, self.country_code_alpha_2, FLAG_IMAGE_EXT)
+        flag_image_url(&self.country_code_alpha_2)
+    }
+
+    /// csv 한 줄 검증. 잘못된 이유를 반환
+    pub fn validate(&self) -> Result<(), String> {
+        let code = &self.country_code_alpha_2;
+        if code.len() != 2 || !code.chars().all(|c| c.is_ascii_lowercase()) {
+            return Err(format!("invalid country_code_alpha_2: {:?}", code));
+        }
+        if self.answer.trim().is_empty() {
+            return Err(format!("empty country_name ({})", code));
+        }
+        if self.capital.as_deref().is_some_and(|capital| capital.trim().is_empty()) {
+            return Err(format!("blank capital ({})", code));
+        }
+
+        Ok(())
+    }
+}
+
+/// `PUBLIC_BASE_URL`이 있으면 직접 서빙하는 `/static/flags/...`, 없으면 CDN
+pub fn flag_image_url(country_code_alpha_2: &str) -> String {
+    let file_name = format!("{}.{}", country_code_alpha_2, FLAG_IMAGE_EXT);
+    config().public_static_url(&format!("{}/{}", FLAG_IMAGE_STATIC_DIR, file_name))
+        .unwrap_or_else(|| format!("{}/{}", FLAG_IMAGE_CDN_PATH, file_name))
+}
+
+// 국기 외의 이미지 퀴즈 (로고, 랜드마크, 인물 등)
+// `image`가 http(s)로 시작하면 그대로 쓰고, 아니면 세트별 base url(config) 뒤에 붙인다
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageQuiz {
+    // ex) "로고". 카테고리 이름으로 쓴다
+    pub set: String,
+
+    // ex) "kakao.png" 또는 "https://..."
+    pub image: String,
+
+    pub alt: String,
+
+    pub answer: String,
+
+    // ex) "이 로고는 어느 회사일까요?". 없으면 기본 문구
+    #[serde(default)]
+    pub question: Option<String>,
+
+    // ex) "1:1", "2:1". 비율에 따라 말풍선 종류가 달라진다
+    #[serde(default)]
+    pub aspect_ratio: Option<String>,
+}
+
+/// 카카오 BasicCard 썸네일은 1:1(fixedRatio) 또는 2:1로 잘린다.
+/// 그 외 비율은 잘리지 않도록 SimpleImage로 보낸다
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageLayout {
+    SquareCard,
+    WideCard,
+    SimpleImage,
+}
+
+impl Question for ImageQuiz {
+    fn info_before(&self, round_header: &str) -> String {
+        format!("{} ({})\n{}", round_header, self.set, self.question())
+    }
+
+    fn is_correct_answer(&self, answer: &str) -> bool {
+        hangul::is_same_answer(&self.answer, answer)
+    }
+
+    fn question_id(&self) -> String {
+        format!("image:{}", stats::stable_hash(&format!("{}:{}", self.set, self.image)))
+    }
+
+    fn reveal_answer(&self) -> String {
+        self.answer.clone()
+    }
+}
+
+impl ImageQuiz {
+    pub fn question(&self) -> &str {
+        self.question.as_deref().unwrap_or("이 사진은 무엇일까요?")
+    }
+
+    pub fn title(&self, round_header: &str) -> String {
+        format!("{} {}", round_header, self.set)
+    }
+
+    pub fn is_absolute_url(&self) -> bool {
+        self.image.starts_with("http://") || self.image.starts_with("https://")
+    }
+
+    /// 상대 경로면 세트 base url, 그것도 없으면 `/static/images/{set}/...`.
+    /// 둘 다 설정되어 있지 않으면 None
+    pub fn image_url(&self) -> Option<String> {
+        if self.is_absolute_url() {
+            return Some(self.image.clone());
+        }
+
+        let image = self.image.trim_start_matches('/');
+        match config().IMAGE_QUIZ_BASE_URLS.get(&self.set) {
+            Some(base_url) => Some(format!("{}/{}", base_url.trim_end_matches('/'), image)),
+            None => config().public_static_url(&format!("{}/{}/{}", IMAGE_QUIZ_STATIC_DIR, self.set, image)),
+        }
+    }
+
+    pub fn layout(&self) -> ImageLayout {
+        match self.aspect_ratio.as_deref().map(str::trim) {
+            Some("1:1") => ImageLayout::SquareCard,
+            Some("2:1") => ImageLayout::WideCard,
+            _ => ImageLayout::SimpleImage,
+        }
+    }
+
+    /// csv 한 줄 검증. 잘못된 이유를 반환
+    pub fn validate(&self) -> Result<(), String> {
+        if self.set.trim().is_empty() || self.answer.trim().is_empty() || self.image.trim().is_empty() {
+            return Err(format!("empty set/image/answer ({:?})", self.image));
+        }
+        if self.image_url().is_none() {
+            return Err(format!("no base url for set {:?} ({:?})", self.set, self.image));
+        }
+
+        Ok(())
+    }
+}
+
+// 국기 데이터의 수도 컬럼으로 출제
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapitalQuiz {
+    pub country_code_alpha_2: String,
+    pub country_name: String,
+    pub capital: String,
+    // false: 나라 -> 수도, true: 수도 -> 나라
+    pub reverse: bool,
+}
+
+impl Question for CapitalQuiz {
+    fn info_before(&self, round_header: &str) -> String {
+        if self.reverse {
+            format!("{} '{}'은(는) 어느 나라의 수도일까요?", round_header, self.capital)
+        } else {
+            format!("{} '{}'의 수도는 어디일까요?", round_header, self.country_name)
+        }
+    }
+
+    fn is_correct_answer(&self, answer: &str) -> bool {
+        hangul::is_same_answer(self.answer(), answer)
+    }
+
+    fn question_id(&self) -> String {
+        let prefix = if self.reverse { "capital_reverse" } else { "capital" };
+        format!("{}:{}", prefix, self.country_code_alpha_2)
+    }
+
+    fn reveal_answer(&self) -> String {
+        self.answer().to_string()
+    }
+}
+
+impl CapitalQuiz {
+    pub fn answer(&self) -> &str {
+        if self.reverse { &self.country_name } else { &self.capital }
+    }
+
+    pub fn image_url(&self) -> String {
+        flag_image_url(&self.country_code_alpha_2)
+    }
+}
+
+// 나라 이름을 보여주고 여러 국기 중 고르기
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlagChoiceQuiz {
+    pub country_name: String,
+    // 보기 국기들의 country_code_alpha_2
+    pub choices: Vec<String>,
+    // 0-based
+    pub answer_index: usize,
+}
+
+impl Question for FlagChoiceQuiz {
+    fn info_before(&self, round_header: &str) -> String {
+        format!("{} '{}'의 국기는 몇 번일까요? (정답 N)", round_header, self.country_name)
+    }
+
+    /// "2", "2번" 모두 허용 (1-based)
+    fn is_correct_answer(&self, answer: &str) -> bool {
+        answer.trim_end_matches('번')
+            .trim()
+            .parse::<usize>()
+            .is_ok_and(|n| n == self.answer_index + 1)
+    }
+
+    fn question_id(&self) -> String {
+        format!("flag_choice:{}", self.choices[self.answer_index])
+    }
+
+    fn reveal_answer(&self) -> String {
+        format!("{}번", self.answer_index + 1)
+    }
+}
+
+impl FlagChoiceQuiz {
+    pub fn choice_image_urls(&self) -> Vec<String> {
+        self.choices.iter()
+            .map(|code| flag_image_url(code))
+            .collect()
+    }
+}
+
+// 기존 문제(텍스트/국기)의 정답에서 초성을 뽑아 출제
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChosungQuiz {
+    // 정답을 가져온 원래 카테고리. ex) "고사성어", "국기"
+    pub category: String,
+    pub answer: String,
+}
+
+impl ChosungQuiz {
+    pub fn new(category: String, answer: String) -> Self {
+        Self { category, answer }
+    }
+
+    /// 초성 문제로 낼 수 있는 정답인지 (전부 한글 + 두 글자 이상)
+    pub fn is_eligible_answer(answer: &str) -> bool {
+        hangul::is_all_hangul(answer) && hangul::normalize(answer).chars().count() >= 2
+    }
+
+    pub fn prompt(&self) -> String {
+        hangul::to_chosung(&self.answer)
+    }
+}
+
+impl Question for ChosungQuiz {
+    fn info_before(&self, round_header: &str) -> String {
+        format!("{} (초성 - {})\n{}", round_header, self.category, self.prompt())
+    }
+
+    fn is_correct_answer(&self, answer: &str) -> bool {
+        hangul::is_same_answer(&self.answer, answer)
+    }
+
+    fn question_id(&self) -> String {
+        format!("chosung:{}", stats::stable_hash(&format!("{}:{}", self.category, self.answer)))
+    }
+
+    fn reveal_answer(&self) -> String {
+        self.answer.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_answers_ignore_spaces_and_case() {
+        let quiz = Quiz {
+            category: "수도".to_string(),
+            question: "오클랜드가 있는 나라는?".to_string(),
+            answer: "뉴질랜드".to_string(),
+            comment: None,
+            difficulty: None,
+        };
+        assert!(quiz.is_correct_answer("뉴 질랜드"));
+        assert!(quiz.is_correct_answer(" 뉴질랜드 "));
+        assert!(!quiz.is_correct_answer("뉴질랜"));
+
+        let flag = FlagQuiz {
+            country_code_alpha_2: "nz".to_string(),
+            answer: "뉴 질랜드".to_string(),
+            capital: Some("웰링턴".to_string()),
+            continent: None,
+        };
+        assert!(flag.is_correct_answer("뉴질랜드"));
+
+        let capital = CapitalQuiz {
+            country_code_alpha_2: "nz".to_string(),
+            country_name: "뉴질랜드".to_string(),
+            capital: "웰링턴".to_string(),
+            reverse: true,
+        };
+        assert!(capital.is_correct_answer("뉴 질랜드"));
+
+        let image = ImageQuiz {
+            set: "로고".to_string(),
+            image: "https://example.com/kakao.png".to_string(),
+            alt: "카카오 로고".to_string(),
+            answer: "Kakao Talk".to_string(),
+            question: None,
+            aspect_ratio: None,
+        };
+        assert!(image.is_correct_answer("kakaotalk"));
+
+        let chosung = ChosungQuiz::new("국기".to_string(), "뉴질랜드".to_string());
+        assert!(chosung.is_correct_answer("뉴 질랜드"));
     }
 }
@@ -0,0 +1,158 @@
+//! 숫자 정답 파싱
+//!
+//! "1000", "1,000", "천", "1천", "만 2천", "삼백오십", "-5", "마이너스 오" 등을 정수로 바꾼다.
+
+// 만 미만 자리 단위
+fn small_unit(c: char) -> Option<i64> {
+    match c {
+        '십' => Some(10),
+        '백' => Some(100),
+        '천' => Some(1_000),
+        _ => None,
+    }
+}
+
+// 네 자리마다 끊는 큰 단위
+fn big_unit(c: char) -> Option<i64> {
+    match c {
+        '만' => Some(10_000),
+        '억' => Some(100_000_000),
+        '조' => Some(1_000_000_000_000),
+        _ => None,
+    }
+}
+
+fn korean_digit(c: char) -> Option<i64> {
+    match c {
+        '영' | '공' => Some(0),
+        '일' => Some(1),
+        '이' => Some(2),
+        '삼' => Some(3),
+        '사' => Some(4),
+        '오' => Some(5),
+        '육' => Some(6),
+        '칠' => Some(7),
+        '팔' => Some(8),
+        '구' => Some(9),
+        _ => None,
+    }
+}
+
+/// 숫자로 읽을 수 없으면 None
+pub fn parse_number(s: &str) -> Option<i64> {
+    let s: String = s.chars()
+        .filter(|c| !c.is_whitespace() && *c != ',')
+        .collect();
+
+    let (negative, s) = if let Some(rest) = s.strip_prefix('-') {
+        (true, rest)
+    } else if let Some(rest) = s.strip_prefix("마이너스") {
+        (true, rest)
+    } else {
+        (false, s.as_str())
+    };
+    // 부호는 앞에 하나만 ("--3", "-+3"은 숫자가 아니다)
+    if s.is_empty() || s.starts_with(['-', '+']) {
+        return None;
+    }
+
+    let value = match s.parse::<i64>() {
+        Ok(value) => value,
+        Err(_) => parse_korean_number(s)?,
+    };
+
+    Some(if negative { -value } else { value })
+}
+
+/// "3만 2천", "삼백오십"처럼 아라비아 숫자와 한글 단위가 섞인 경우
+fn parse_korean_number(s: &str) -> Option<i64> {
+    let mut total: i64 = 0;
+    // 만 미만 부분
+    let mut section: i64 = 0;
+    // 아직 단위가 붙지 않은 숫자
+    let mut current: Option<i64> = None;
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if let Some(digit) = c.to_digit(10) {
+            let mut value = digit as i64;
+            while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                value = value.checked_mul(10)?.checked_add(digit as i64)?;
+                chars.next();
+            }
+            if current.is_some() {
+                return None;
+            }
+            current = Some(value);
+        } else if let Some(digit) = korean_digit(c) {
+            if current.is_some() {
+                return None;
+            }
+            current = Some(digit);
+        } else if let Some(unit) = small_unit(c) {
+            // "천" == "일천"
+            section = section.checked_add(current.take().unwrap_or(1).checked_mul(unit)?)?;
+        } else if let Some(unit) = big_unit(c) {
+            let mut value = section.checked_add(current.take().unwrap_or(0))?;
+            // "만" == "일만"
+            if value == 0 {
+                value = 1;
+            }
+            total = total.checked_add(value.checked_mul(unit)?)?;
+            section = 0;
+        } else {
+            return None;
+        }
+    }
+
+    total.checked_add(section)?.checked_add(current.unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_digits_and_korean_units() {
+        let cases = [
+            ("1000", Some(1_000)),
+            ("1,000", Some(1_000)),
+            (" 12 345 ", Some(12_345)),
+            ("천", Some(1_000)),
+            ("1천", Some(1_000)),
+            ("만 2천", Some(12_000)),
+            ("3만 2천", Some(32_000)),
+            ("삼백오십", Some(350)),
+            ("이천이십육", Some(2_026)),
+            ("일억", Some(100_000_000)),
+            ("영", Some(0)),
+            ("-3", Some(-3)),
+            ("마이너스 오", Some(-5)),
+            ("마이너스 1,000", Some(-1_000)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_number(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_garbage_and_overflow() {
+        let cases = [
+            "",
+            "-",
+            "마이너스",
+            "abc",
+            "12a",
+            "오오",
+            "3삼",
+            "천원",
+            "--3",
+            "마이너스 -3",
+            "99999999999999999999",
+            "9999999조 9999999조",
+        ];
+        for input in cases {
+            assert_eq!(parse_number(input), None, "{:?}", input);
+        }
+    }
+}
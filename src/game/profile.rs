@@ -0,0 +1,280 @@
+//! 사용자 프로필: 닉네임, 그리고 `ScoreStore`에 함께 있는 누적 점수와 사용자별 기록
+//!
+//! 멘션은 카카오톡 말풍선 안에서만 보여서, 순위표나 로그처럼 멘션을 못 쓰는 곳에는 닉네임을 쓴다.
+//! 닉네임이 없으면 user_id 앞부분을 보여준다.
+//!
+//! Redis key structure
+//! ---
+//! - key: `profile:{user_id}` (HASH) / field: `nickname`
+//! - key: `nicknames` (HASH) / field: 소문자 닉네임 / value: `{user_id}` (중복 확인용)
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use redis::AsyncCommands;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::{Error, Result};
+use crate::conn::RedisConnectionPool;
+use crate::game::score::{CorrectAnswer, ScoreStore};
+use crate::game::state::GroupKey;
+
+const REDIS_PROFILE_KEY_PREFIX: &str = "profile:";
+const REDIS_NICKNAMES_KEY: &str = "nicknames";
+
+pub const RANKING_SIZE: usize = 10;
+
+const NICKNAME_MIN_CHARS: usize = 2;
+const NICKNAME_MAX_CHARS: usize = 10;
+// 닉네임 어디에든 들어 있으면 안 되는 말 (소문자로 비교)
+const BANNED_NICKNAME_WORDS: [&str; 12] = [
+    "시발", "씨발", "ㅅㅂ", "병신", "ㅂㅅ", "개새", "좆", "존나",
+    "fuck", "shit", "bitch",
+    "관리자",
+];
+// 닉네임이 없을 때 보여줄 user_id 길이
+const USER_ID_DISPLAY_CHARS: usize = 6;
+
+/// 닉네임이 있으면 닉네임, 없으면 user_id 앞부분
+pub fn display_name(user_id: &str, nickname: Option<&str>) -> String {
+    match nickname {
+        Some(nickname) => nickname.to_string(),
+        None => user_id.chars().take(USER_ID_DISPLAY_CHARS).collect(),
+    }
+}
+
+/// 앞뒤 공백을 뗀 닉네임. 한글, 영문, 숫자만 2~10자
+pub fn validate_nickname(nickname: &str) -> Result<String> {
+    let nickname = nickname.trim();
+    let chars = nickname.chars().count();
+    if !(NICKNAME_MIN_CHARS..=NICKNAME_MAX_CHARS).contains(&chars) {
+        return Err(Error::NicknameInvalid("닉네임은 2~10자로 지어 주세요"));
+    }
+    if !nickname.chars().all(|c| c.is_ascii_alphanumeric() || ('가'..='힣').contains(&c) || ('ㄱ'..='ㅣ').contains(&c)) {
+        return Err(Error::NicknameInvalid("닉네임에는 한글, 영문, 숫자만 쓸 수 있어요"));
+    }
+
+    let lowercase = nickname.to_lowercase();
+    if BANNED_NICKNAME_WORDS.iter().any(|word| lowercase.contains(word)) {
+        return Err(Error::NicknameInvalid("쓸 수 없는 말이 들어 있어요"));
+    }
+
+    Ok(nickname.to_string())
+}
+
+/// "내 정보"
+pub struct Profile {
+    pub nickname: Option<String>,
+    pub score: u32,
+    // (카테고리, 맞힌 개수). 가장 많이 맞힌 카테고리
+    pub favorite_category: Option<(String, u64)>,
+}
+
+/// "내 기록", admin 조회
+#[derive(Debug, Serialize)]
+pub struct UserStats {
+    pub user_id: String,
+    pub nickname: Option<String>,
+    pub score: u32,
+    pub games_played: u64,
+    pub first_places: u64,
+    pub correct_by_category: HashMap<String, u64>,
+    // 맞힌 적이 없으면 None
+    pub fastest_answer_ms: Option<u64>,
+    pub longest_streak: u64,
+}
+
+impl UserStats {
+    pub fn total_correct(&self) -> u64 {
+        self.correct_by_category.values().sum()
+    }
+
+    /// 많이 맞힌 순. 같으면 이름 순
+    pub fn categories_by_correct(&self) -> Vec<(&str, u64)> {
+        let mut categories: Vec<(&str, u64)> = self.correct_by_category.iter()
+            .map(|(category, correct)| (category.as_str(), *correct))
+            .collect();
+        categories.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        categories
+    }
+}
+
+#[derive(Clone)]
+pub struct ProfileStore {
+    pool: RedisConnectionPool,
+    scores: Arc<dyn ScoreStore>,
+    // user_id -> 닉네임. 로그처럼 Redis를 기다릴 수 없는 곳에서 쓴다
+    nicknames: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl ProfileStore {
+    pub fn new(pool: RedisConnectionPool, scores: Arc<dyn ScoreStore>) -> Self {
+        Self {
+            pool,
+            scores,
+            nicknames: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    fn redis_key(user_id: &str) -> String {
+        format!("{}{}", REDIS_PROFILE_KEY_PREFIX, user_id)
+    }
+
+    /// 로그용. 아는 닉네임이 있으면 `닉네임(user_id 앞부분)`
+    pub fn label(&self, user_id: &str) -> String {
+        let short_id = display_name(user_id, None);
+        match self.nicknames.read().unwrap().get(user_id) {
+            Some(nickname) => format!("{}({})", nickname, short_id),
+            None => short_id,
+        }
+    }
+
+    /// 알고 있는 닉네임으로만 (Redis를 읽지 않는다)
+    pub fn cached_display_name(&self, user_id: &str) -> String {
+        display_name(user_id, self.nicknames.read().unwrap().get(user_id).map(String::as_str))
+    }
+
+    /// 다른 사람이 쓰는 닉네임은 쓸 수 없다 (대소문자 무시). 바꾸면 예전 닉네임은 풀린다
+    pub async fn set_nickname(&self, user_id: &str, nickname: &str) -> Result<String> {
+        let nickname = validate_nickname(nickname)?;
+        let nickname_key = nickname.to_lowercase();
+
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let claimed: bool = conn.hset_nx(REDIS_NICKNAMES_KEY, &nickname_key, user_id)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_NICKNAMES_KEY.to_string()))?;
+        if !claimed {
+            let owner: Option<String> = conn.hget(REDIS_NICKNAMES_KEY, &nickname_key)
+                .await
+                .map_err(|_| Error::RedisCommandFail(REDIS_NICKNAMES_KEY.to_string()))?;
+            if owner.as_deref() != Some(user_id) {
+                return Err(Error::NicknameTaken(nickname));
+            }
+        }
+
+        let redis_key = Self::redis_key(user_id);
+        let previous: Option<String> = conn.hget(&redis_key, "nickname")
+            .await
+            .map_err(|_| Error::RedisCommandFail(redis_key.clone()))?;
+
+        let mut pipe = redis::pipe();
+        pipe.hset(&redis_key, "nickname", &nickname).ignore();
+        if let Some(previous) = previous.map(|previous| previous.to_lowercase()).filter(|previous| *previous != nickname_key) {
+            pipe.hdel(REDIS_NICKNAMES_KEY, previous).ignore();
+        }
+        pipe.query_async::<_, ()>(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(redis_key))?;
+
+        debug!("{:<12} - nickname {}, user_id: {}", "PROFILE", nickname, user_id);
+        self.nicknames.write().unwrap().insert(user_id.to_string(), nickname.clone());
+        Ok(nickname)
+    }
+
+    /// 닉네임이 있는 사람만 들어 있다
+    pub async fn nicknames(&self, user_ids: &[String]) -> Result<HashMap<String, String>> {
+        if user_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let mut pipe = redis::pipe();
+        for user_id in user_ids {
+            pipe.hget(Self::redis_key(user_id), "nickname");
+        }
+        let nicknames: Vec<Option<String>> = pipe.query_async(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_PROFILE_KEY_PREFIX.to_string()))?;
+
+        let nicknames: HashMap<String, String> = user_ids.iter()
+            .zip(nicknames)
+            .filter_map(|(user_id, nickname)| Some((user_id.clone(), nickname?)))
+            .collect();
+        self.nicknames.write().unwrap().extend(nicknames.clone());
+        Ok(nicknames)
+    }
+
+    /// 맞힌 사람과 방의 누적 점수를 올리고 기록을 남긴다. 사용자의 누적 점수를 돌려준다
+    pub async fn add_correct(&self, correct: CorrectAnswer<'_>) -> Result<u32> {
+        self.scores.add_correct(correct).await
+    }
+
+    /// 끝까지 진행된 게임. 응답을 늦추지 않도록 백그라운드에서 기록한다
+    pub fn record_game(&self, players: Vec<String>, winner: Option<String>) {
+        if players.is_empty() && winner.is_none() {
+            return;
+        }
+
+        let scores = self.scores.clone();
+        tokio::spawn(async move {
+            if let Err(e) = scores.add_game(&players, winner.as_deref()).await {
+                warn!("{:<12} - record failed: {:?}", "PROFILE", e);
+            }
+        });
+    }
+
+    pub async fn user_stats(&self, user_id: &str) -> Result<UserStats> {
+        let nickname = self.nickname(user_id).await?;
+        let records = self.scores.user_records(user_id).await?;
+
+        Ok(UserStats {
+            user_id: user_id.to_string(),
+            nickname,
+            score: records.score,
+            games_played: records.games_played,
+            first_places: records.first_places,
+            correct_by_category: records.correct_by_category,
+            fastest_answer_ms: records.fastest_answer_ms,
+            longest_streak: records.longest_streak,
+        })
+    }
+
+    pub async fn profile(&self, user_id: &str) -> Result<Profile> {
+        let nickname = self.nickname(user_id).await?;
+        let records = self.scores.user_records(user_id).await?;
+
+        let favorite_category = records.correct_by_category.into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)));
+
+        Ok(Profile {
+            nickname,
+            score: records.score,
+            favorite_category,
+        })
+    }
+
+    async fn nickname(&self, user_id: &str) -> Result<Option<String>> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let redis_key = Self::redis_key(user_id);
+        let nickname: Option<String> = conn.hget(&redis_key, "nickname")
+            .await
+            .map_err(|_| Error::RedisCommandFail(redis_key))?;
+
+        if let Some(nickname) = &nickname {
+            self.nicknames.write().unwrap().insert(user_id.to_string(), nickname.clone());
+        }
+        Ok(nickname)
+    }
+
+    /// (사용자 순위, 방 순위). 맞힌 적이 없으면 None
+    pub async fn ranks(&self, user_id: &str, group_key: &GroupKey) -> Result<(Option<usize>, Option<usize>)> {
+        let user_rank = self.scores.user_rank(user_id).await?;
+        let group_rank = self.scores.group_rank(group_key).await?;
+        Ok((user_rank.map(|(_, rank)| rank), group_rank.map(|(_, rank)| rank)))
+    }
+
+    /// 누적 점수 순위표. (user_id, 누적 점수)
+    pub async fn top_scores(&self, size: usize) -> Result<Vec<(String, u32)>> {
+        self.scores.top_users(size).await
+    }
+}
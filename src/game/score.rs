@@ -0,0 +1,337 @@
+//! 누적 점수, 순위와 사용자별 기록 (게임 수, 1등 횟수, 카테고리별 정답, 가장 빠른 정답, 최고 연속 정답)
+//!
+//! 맞힐 때마다 사람과 방의 누적 점수가 1씩 오르고, 같은 곳에 그 사람의 기록도 남긴다.
+//! 순위는 점수 높은 순이고, 점수가 같으면 Redis ZSET 순서(멤버 역순)를 따른다.
+//!
+//! Redis key structure
+//! ---
+//! - key: `user_scores` (ZSET) / member: `{user_id}` / score: 누적 점수
+//! - key: `group_scores` (ZSET) / member: `{group_key}` / score: 누적 점수
+//! - key: `profile:{user_id}:categories` (HASH) / field: `{category}` / value: 맞힌 개수
+//! - key: `profile:{user_id}:stats` (HASH) / fields: `games_played`, `first_places`, `fastest_ms`, `longest_streak`
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::AsyncCommands;
+
+use crate::{Error, Result};
+use crate::config::GameStoreConfig;
+use crate::conn::RedisConnectionPool;
+use crate::game::state::GroupKey;
+
+const REDIS_USER_SCORES_KEY: &str = "user_scores";
+const REDIS_GROUP_SCORES_KEY: &str = "group_scores";
+const REDIS_PROFILE_KEY_PREFIX: &str = "profile:";
+// 기존 값보다 작을 때(ARGV[3] == "min") 또는 클 때("max")만 HSET
+const REDIS_HSET_IF_SCRIPT: &str = r#"
+local current = tonumber(redis.call("hget", KEYS[1], ARGV[1]))
+local value = tonumber(ARGV[2])
+if current == nil or (ARGV[3] == "min" and value < current) or (ARGV[3] == "max" and value > current) then
+    redis.call("hset", KEYS[1], ARGV[1], ARGV[2])
+    return 1
+end
+return 0
+"#;
+
+/// 맞힌 문제 하나
+pub struct CorrectAnswer<'a> {
+    pub user_id: &'a str,
+    pub group_key: &'a GroupKey,
+    pub category: &'a str,
+    pub answer_time: Duration,
+    // 이번 문제까지 연속으로 맞힌 수
+    pub streak: u32,
+}
+
+/// 한 사람의 누적 점수와 기록
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserRecords {
+    pub score: u32,
+    pub games_played: u64,
+    pub first_places: u64,
+    pub correct_by_category: HashMap<String, u64>,
+    // 맞힌 적이 없으면 None
+    pub fastest_answer_ms: Option<u64>,
+    pub longest_streak: u64,
+}
+
+#[async_trait]
+pub trait ScoreStore: Send + Sync {
+    /// 맞힌 사람과 방의 누적 점수 +1, 카테고리별 정답 +1, 가장 빠른 정답과 최고 연속 정답 갱신.
+    /// 사용자의 누적 점수를 돌려준다
+    async fn add_correct(&self, correct: CorrectAnswer<'_>) -> Result<u32>;
+    /// 끝까지 진행된 게임. 참가자마다 게임 수 +1, 1등은 1등 횟수 +1
+    async fn add_game(&self, players: &[String], winner: Option<&str>) -> Result<()>;
+    async fn user_records(&self, user_id: &str) -> Result<UserRecords>;
+    /// (누적 점수, 순위). 맞힌 적이 없으면 None
+    async fn user_rank(&self, user_id: &str) -> Result<Option<(u32, usize)>>;
+    /// (방 누적 점수, 순위). 맞힌 적이 없으면 None
+    async fn group_rank(&self, group_key: &GroupKey) -> Result<Option<(u32, usize)>>;
+    /// 누적 점수 높은 순 (user_id, 누적 점수)
+    async fn top_users(&self, size: usize) -> Result<Vec<(String, u32)>>;
+}
+
+/// `GAME_STORE`가 메모리면 점수와 기록도 메모리에 (재시작하면 사라진다). 아니면 Redis
+pub fn score_store(store_config: &GameStoreConfig, pool: RedisConnectionPool) -> Arc<dyn ScoreStore> {
+    match store_config {
+        GameStoreConfig::Memory => Arc::new(InMemoryScoreStore::default()),
+        GameStoreConfig::Redis | GameStoreConfig::File(_) => Arc::new(RedisScoreStore { pool }),
+    }
+}
+
+pub struct RedisScoreStore {
+    pool: RedisConnectionPool,
+}
+
+impl RedisScoreStore {
+    fn categories_redis_key(user_id: &str) -> String {
+        format!("{}{}:categories", REDIS_PROFILE_KEY_PREFIX, user_id)
+    }
+
+    fn stats_redis_key(user_id: &str) -> String {
+        format!("{}{}:stats", REDIS_PROFILE_KEY_PREFIX, user_id)
+    }
+
+    async fn rank(&self, redis_key: &str, member: &str) -> Result<Option<(u32, usize)>> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let (score, rank): (Option<u32>, Option<usize>) = redis::pipe()
+            .zscore(redis_key, member)
+            .zrevrank(redis_key, member)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(redis_key.to_string()))?;
+
+        Ok(score.zip(rank.map(|rank| rank + 1)))
+    }
+}
+
+#[async_trait]
+impl ScoreStore for RedisScoreStore {
+    async fn add_correct(&self, correct: CorrectAnswer<'_>) -> Result<u32> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let stats_redis_key = Self::stats_redis_key(correct.user_id);
+        let mut pipe = redis::pipe();
+        pipe.zincr(REDIS_USER_SCORES_KEY, correct.user_id, 1)
+            .zincr(REDIS_GROUP_SCORES_KEY, correct.group_key, 1).ignore()
+            .hincr(Self::categories_redis_key(correct.user_id), correct.category, 1).ignore();
+        for (field, value, compare) in [
+            ("fastest_ms", correct.answer_time.as_millis() as u64, "min"),
+            ("longest_streak", correct.streak as u64, "max"),
+        ] {
+            pipe.cmd("EVAL")
+                .arg(REDIS_HSET_IF_SCRIPT)
+                .arg(1)
+                .arg(&stats_redis_key)
+                .arg(field)
+                .arg(value)
+                .arg(compare)
+                .ignore();
+        }
+
+        let (score,): (u32,) = pipe.query_async(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_USER_SCORES_KEY.to_string()))?;
+        Ok(score)
+    }
+
+    async fn add_game(&self, players: &[String], winner: Option<&str>) -> Result<()> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let mut pipe = redis::pipe();
+        for user_id in players {
+            pipe.hincr(Self::stats_redis_key(user_id), "games_played", 1).ignore();
+        }
+        if let Some(winner) = winner {
+            pipe.hincr(Self::stats_redis_key(winner), "first_places", 1).ignore();
+        }
+        pipe.query_async::<_, ()>(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_PROFILE_KEY_PREFIX.to_string()))
+    }
+
+    async fn user_records(&self, user_id: &str) -> Result<UserRecords> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let stats_redis_key = Self::stats_redis_key(user_id);
+        let (score, correct_by_category, stats): (Option<u32>, HashMap<String, u64>, HashMap<String, u64>) = redis::pipe()
+            .zscore(REDIS_USER_SCORES_KEY, user_id)
+            .hgetall(Self::categories_redis_key(user_id))
+            .hgetall(&stats_redis_key)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(stats_redis_key))?;
+
+        let field = |name: &str| stats.get(name).copied();
+        Ok(UserRecords {
+            score: score.unwrap_or(0),
+            games_played: field("games_played").unwrap_or(0),
+            first_places: field("first_places").unwrap_or(0),
+            correct_by_category,
+            fastest_answer_ms: field("fastest_ms"),
+            longest_streak: field("longest_streak").unwrap_or(0),
+        })
+    }
+
+    async fn user_rank(&self, user_id: &str) -> Result<Option<(u32, usize)>> {
+        self.rank(REDIS_USER_SCORES_KEY, user_id).await
+    }
+
+    async fn group_rank(&self, group_key: &GroupKey) -> Result<Option<(u32, usize)>> {
+        self.rank(REDIS_GROUP_SCORES_KEY, group_key).await
+    }
+
+    async fn top_users(&self, size: usize) -> Result<Vec<(String, u32)>> {
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        conn.zrevrange_withscores(REDIS_USER_SCORES_KEY, 0, size as isize - 1)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_USER_SCORES_KEY.to_string()))
+    }
+}
+
+/// 재시작하면 사라진다. 순서는 Redis ZSET과 같게
+#[derive(Default)]
+pub struct InMemoryScoreStore {
+    users: Mutex<HashMap<String, UserRecords>>,
+    groups: Mutex<HashMap<GroupKey, u32>>,
+}
+
+impl InMemoryScoreStore {
+    // ZREVRANGE 순서: 점수 높은 순, 같으면 멤버 역순. 맞힌 적이 없는 사람은 빠진다
+    fn ranked(scores: impl Iterator<Item = (String, u32)>) -> Vec<(String, u32)> {
+        let mut ranked: Vec<(String, u32)> = scores.filter(|(_, score)| *score > 0).collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+        ranked
+    }
+
+    fn rank(ranked: Vec<(String, u32)>, member: &str) -> Option<(u32, usize)> {
+        ranked.into_iter()
+            .enumerate()
+            .find(|(_, (other, _))| other == member)
+            .map(|(rank, (_, score))| (score, rank + 1))
+    }
+
+    fn ranked_users(&self) -> Vec<(String, u32)> {
+        Self::ranked(self.users.lock().unwrap().iter().map(|(user_id, records)| (user_id.clone(), records.score)))
+    }
+}
+
+#[async_trait]
+impl ScoreStore for InMemoryScoreStore {
+    async fn add_correct(&self, correct: CorrectAnswer<'_>) -> Result<u32> {
+        *self.groups.lock().unwrap().entry(correct.group_key.clone()).or_insert(0) += 1;
+
+        let mut users = self.users.lock().unwrap();
+        let records = users.entry(correct.user_id.to_string()).or_default();
+        records.score += 1;
+        *records.correct_by_category.entry(correct.category.to_string()).or_insert(0) += 1;
+        let answer_ms = correct.answer_time.as_millis() as u64;
+        records.fastest_answer_ms = Some(records.fastest_answer_ms.map_or(answer_ms, |fastest| fastest.min(answer_ms)));
+        records.longest_streak = records.longest_streak.max(correct.streak as u64);
+        Ok(records.score)
+    }
+
+    async fn add_game(&self, players: &[String], winner: Option<&str>) -> Result<()> {
+        let mut users = self.users.lock().unwrap();
+        for user_id in players {
+            users.entry(user_id.clone()).or_default().games_played += 1;
+        }
+        if let Some(winner) = winner {
+            users.entry(winner.to_string()).or_default().first_places += 1;
+        }
+        Ok(())
+    }
+
+    async fn user_records(&self, user_id: &str) -> Result<UserRecords> {
+        Ok(self.users.lock().unwrap().get(user_id).cloned().unwrap_or_default())
+    }
+
+    async fn user_rank(&self, user_id: &str) -> Result<Option<(u32, usize)>> {
+        Ok(Self::rank(self.ranked_users(), user_id))
+    }
+
+    async fn group_rank(&self, group_key: &GroupKey) -> Result<Option<(u32, usize)>> {
+        let ranked = Self::ranked(self.groups.lock().unwrap().iter().map(|(group_key, score)| (group_key.clone(), *score)));
+        Ok(Self::rank(ranked, group_key))
+    }
+
+    async fn top_users(&self, size: usize) -> Result<Vec<(String, u32)>> {
+        Ok(self.ranked_users().into_iter().take(size).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn correct<'a>(user_id: &'a str, group_key: &'a GroupKey, answer_ms: u64, streak: u32) -> CorrectAnswer<'a> {
+        CorrectAnswer {
+            user_id,
+            group_key,
+            category: "수학",
+            answer_time: Duration::from_millis(answer_ms),
+            streak,
+        }
+    }
+
+    #[tokio::test]
+    async fn ranks_by_score_like_a_zset() {
+        let store = InMemoryScoreStore::default();
+        let room = "room".to_string();
+        for user_id in ["a", "b", "b", "c", "c", "c"] {
+            store.add_correct(correct(user_id, &room, 1000, 1)).await.unwrap();
+        }
+        store.add_correct(correct("a", &"other".to_string(), 1000, 1)).await.unwrap();
+        // 게임만 하고 맞힌 적 없는 사람은 순위에 없다
+        store.add_game(&["d".to_string()], None).await.unwrap();
+
+        assert_eq!(store.top_users(2).await.unwrap(), vec![("c".to_string(), 3), ("b".to_string(), 2)]);
+        assert_eq!(store.user_rank("c").await.unwrap(), Some((3, 1)));
+        // 같은 점수면 멤버 역순 (ZREVRANK)
+        assert_eq!(store.user_rank("a").await.unwrap(), Some((2, 3)));
+        assert_eq!(store.user_rank("d").await.unwrap(), None);
+
+        assert_eq!(store.group_rank(&room).await.unwrap(), Some((6, 1)));
+        assert_eq!(store.group_rank(&"other".to_string()).await.unwrap(), Some((1, 2)));
+    }
+
+    #[tokio::test]
+    async fn keeps_records_with_the_score() {
+        let store = InMemoryScoreStore::default();
+        let room = "room".to_string();
+        store.add_correct(correct("a", &room, 3000, 1)).await.unwrap();
+        store.add_correct(correct("a", &room, 1500, 2)).await.unwrap();
+        store.add_correct(correct("a", &room, 2000, 1)).await.unwrap();
+        store.add_game(&["a".to_string(), "b".to_string()], Some("a")).await.unwrap();
+
+        let records = store.user_records("a").await.unwrap();
+        assert_eq!(records.score, 3);
+        assert_eq!(records.games_played, 1);
+        assert_eq!(records.first_places, 1);
+        assert_eq!(records.correct_by_category["수학"], 3);
+        assert_eq!(records.fastest_answer_ms, Some(1500));
+        assert_eq!(records.longest_streak, 2);
+
+        assert_eq!(store.user_records("b").await.unwrap().games_played, 1);
+        assert_eq!(store.user_records("nobody").await.unwrap(), UserRecords::default());
+    }
+}
@@ -12,120 +12,724 @@
 //! ZADD
 //! ZRANK
 
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
-use redis::AsyncCommands;
-use tokio::sync::RwLock;
-use tracing::debug;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Mutex, RwLock};
+use tracing::{debug, warn};
 
 use crate::{Error, Result};
+use crate::clock::Clock;
+use crate::config::config;
 use crate::conn::RedisConnectionPool;
-use crate::game::db::{flag_quiz_db, QUIZ_CATEGORIES, quiz_db, QuizType};
-use crate::game::model::Quiz;
-use crate::game::temp_inmemory_db::{SCORES_BY_GROUP, SCORES_BY_USER};
+use crate::event::{Event, EventClient};
+use crate::game::actor::{Expiry, ExpirySender, GameHandle};
+use crate::game::challenge::{ChallengeBoard, RoomResult, CHALLENGE_WINDOW};
+use crate::game::daily::{DailyQuiz, DailyStore};
+use crate::game::profile::ProfileStore;
+use crate::game::score::{CorrectAnswer, ScoreStore};
+use crate::game::db::{get_random_quiz, supports_difficulty, QuizType};
+use crate::game::mode::{GameMode, GameSummary, SURVIVAL_MAX_ROUNDS};
+use crate::game::model::{Difficulty, DifficultyMode};
+use crate::game::stats::{QuestionStatsStore, StatsEvent};
+use crate::game::stopwatch::Stopwatch;
+use crate::game::store::{self, GameStore, RoundClaim};
+use crate::game::team::{Team, Teams};
+use crate::skill::{escape_mentions, Mentions};
 
 pub type GroupKey = String;
 
-const REDIS_USER_SCORES_KEY: &str = "user_scores";
-const REDIS_GROUP_SCORES_KEY: &str = "group_scores";
-
 // XXX: is this right way?
 #[derive(Clone)]
 pub struct GameManager {
-    games: Arc<RwLock<HashMap<GroupKey, Mutex<Game>>>>,
+    // 방마다 게임 task (`GameHandle`). 목록을 바꿀 때만 잠근다
+    games: Arc<RwLock<HashMap<GroupKey, GameHandle>>>,
     
     // pool: RedisConnectionPool,  // to-be-done
     
     // for now, just use static
+
+    stats: QuestionStatsStore,
+    challenges: ChallengeBoard,
+    events: Arc<dyn EventClient>,
+    daily: DailyQuiz,
+    profiles: ProfileStore,
+    lobbies: Arc<Mutex<HashMap<GroupKey, Lobby>>>,
+    // 오래 방치돼서 자동 종료한 게임 수 (모니터링용)
+    evicted_games: Arc<AtomicU64>,
+    // 재시작해도 이어가도록 바뀔 때마다 저장
+    store: Arc<dyn GameStore>,
+    // 여러 인스턴스일 때는 `store`가 원본. 방마다 잠그고 매번 읽어 온다
+    distributed: bool,
+    // 게임 task가 시간이 지나 끝내야 한다고 알릴 때
+    expiry_tx: ExpirySender,
 }
 
 impl GameManager {
-    pub fn new(pool: RedisConnectionPool) -> Result<Self> {
-        Ok(Self {
+    pub fn new(pool: RedisConnectionPool, events: Arc<dyn EventClient>, clock: Arc<dyn Clock>, store: Arc<dyn GameStore>, scores: Arc<dyn ScoreStore>, daily: Arc<dyn DailyStore>, distributed: bool) -> Result<Self> {
+        let (expiry_tx, expiry_rx) = mpsc::unbounded_channel();
+        let gm = Self {
             games: Arc::new(RwLock::new(HashMap::new())),
             // pool,
-        })
+            stats: QuestionStatsStore::new(pool.clone()),
+            challenges: ChallengeBoard::default(),
+            events,
+            daily: DailyQuiz::new(daily, clock),
+            profiles: ProfileStore::new(pool, scores),
+            lobbies: Arc::new(Mutex::new(HashMap::new())),
+            evicted_games: Arc::new(AtomicU64::new(0)),
+            store,
+            distributed,
+            expiry_tx,
+        };
+        gm.spawn_expiry_listener(expiry_rx);
+        Ok(gm)
+    }
+
+    fn spawn_game(&self, game: Game) -> GameHandle {
+        GameHandle::spawn(game, self.store.clone(), self.expiry_tx.clone())
+    }
+
+    fn spawn_expiry_listener(&self, mut expiry_rx: mpsc::UnboundedReceiver<(GroupKey, Expiry)>) {
+        let gm = self.clone();
+        tokio::spawn(async move {
+            while let Some((group_key, expiry)) = expiry_rx.recv().await {
+                let gm = gm.clone();
+                tokio::spawn(async move {
+                    match expiry {
+                        Expiry::PauseTimeout => gm.stop_if_paused_for(&group_key, config().PAUSED_GAME_TIMEOUT).await,
+                        Expiry::BlitzEnd => gm.end_blitz(&group_key).await,
+                    }
+                });
+            }
+        });
+    }
+
+    /// 여러 인스턴스일 때는 방을 잠그고 저장소에서 최신 상태를 읽어 온다.
+    /// 돌려받은 잠금을 들고 있는 동안 바꾼 게임은 다른 인스턴스가 건드리지 않는다.
+    ///
+    /// 처리가 늦어져 잠금이 먼저 풀려도, 그 사이 다른 인스턴스가 쓴 게임은 `version`이 더 높아서
+    /// 이 인스턴스의 저장은 `GameBusy`로 거절된다 (fencing)
+    async fn lock_group(&self, group_key: &GroupKey) -> Result<GroupLock> {
+        let mut lock = GroupLock {
+            store: self.store.clone(),
+            group_key: group_key.clone(),
+            token: None,
+        };
+        if !self.distributed {
+            return Ok(lock);
+        }
+
+        lock.token = Some(store::lock(self.store.as_ref(), group_key).await?);
+        let latest = self.store.load(group_key).await?;
+        let mut games = self.games.write().await;
+        match latest {
+            Some(game) => games.insert(group_key.clone(), self.spawn_game(game)),
+            // 다른 인스턴스에서 끝난 게임
+            None => games.remove(group_key),
+        };
+
+        Ok(lock)
+    }
+
+    /// 시작할 때 저장된 게임을 불러온다. 일시정지된 게임은 게임 task가 남은 시간으로 타이머를 다시 건다
+    pub async fn restore_games(&self) -> Result<()> {
+        let restored = self.store.load_all().await?;
+        debug!("{:<12} - restored {} games", "GAME", restored.len());
+
+        let mut games = self.games.write().await;
+        for game in restored {
+            games.insert(game.group_key.clone(), self.spawn_game(game));
+        }
+        Ok(())
+    }
+
+    async fn handle(&self, group_key: &GroupKey) -> Result<GameHandle> {
+        self.games.read().await
+            .get(group_key)
+            .cloned()
+            .ok_or(Error::GameNotFound(group_key.clone()))
+    }
+
+    async fn delete_snapshot(&self, group_key: &GroupKey) {
+        if let Err(e) = self.store.remove(group_key).await {
+            warn!("{:<12} - remove {} failed: {:?}", "GAME", group_key, e);
+        }
+    }
+
+    pub fn stats(&self) -> &QuestionStatsStore {
+        &self.stats
+    }
+
+    pub fn daily(&self) -> &DailyQuiz {
+        &self.daily
+    }
+
+    pub fn profiles(&self) -> &ProfileStore {
+        &self.profiles
+    }
+
+    pub async fn status(&self) -> GameManagerStatus {
+        GameManagerStatus {
+            active_games: self.games.read().await.len(),
+            open_lobbies: self.lobbies.lock().await.len(),
+            evicted_games: self.evicted_games.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 설정한 시간 동안 아무 활동이 없는 게임을 주기적으로 정리한다
+    pub fn spawn_reaper(&self) {
+        let gm = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(IDLE_GAME_REAP_INTERVAL);
+            loop {
+                interval.tick().await;
+                gm.reap_idle_games(config().IDLE_GAME_TIMEOUT).await;
+            }
+        });
+    }
+
+    async fn reap_idle_games(&self, timeout: Duration) {
+        let handles: Vec<(GroupKey, GameHandle)> = self.games.read().await.iter()
+            .map(|(group_key, handle)| (group_key.clone(), handle.clone()))
+            .collect();
+        let mut idle = Vec::new();
+        for (group_key, handle) in handles {
+            if handle.call(move |game| game.idle_for() >= timeout).await.unwrap_or(false) {
+                idle.push(group_key);
+            }
+        }
+
+        for group_key in idle {
+            // 확인하는 사이에 누가 답했을 수도 있으니 다시 본다. 목록은 잠그지 않고
+            let Ok(_lock) = self.lock_group(&group_key).await else {
+                continue;
+            };
+            let Ok(handle) = self.handle(&group_key).await else {
+                continue;
+            };
+            if !handle.call(move |game| game.idle_for() >= timeout).await.unwrap_or(false) {
+                continue;
+            }
+            // 그 사이에 다른 게임으로 바뀌지 않았을 때만 뺀다
+            {
+                let mut games = self.games.write().await;
+                if !games.get(&group_key).is_some_and(|current| current.same_game(&handle)) {
+                    continue;
+                }
+                games.remove(&group_key);
+            }
+            let Ok(game) = handle.stop().await else {
+                continue;
+            };
+            self.delete_snapshot(&group_key).await;
+
+            debug!("{:<12} - evict idle game, group_key: {}", "GAME", group_key);
+            self.evicted_games.fetch_add(1, Ordering::Relaxed);
+            self.stats.record(vec![StatsEvent::Skipped(game.current_quiz.as_question().question_id())]);
+            self.on_game_finished(&game, GameEnd::Stopped).await;
+            self.announce(vec![(group_key, "💤 오랫동안 활동이 없어 게임이 자동 종료되었습니다".to_string())]);
+        }
+    }
+
+    pub async fn start_game(&self, group_key: GroupKey, options: StartOptions) -> Result<Game> {
+        debug!("{:<12} - start_game, group_key: {}, options: {:?}", "GAME", group_key, options);
+        options.check_difficulty()?;
+        if self.lobbies.lock().await.contains_key(&group_key) {
+            return Err(Error::LobbyAlreadyOpen(group_key));
+        }
+        let _lock = self.lock_group(&group_key).await?;
+        let mut games = self.games.write().await;
+        if games.contains_key(&group_key) {
+            return Err(Error::GameAlreadyStarted(group_key));
+        }
+
+        let game = Game::new(group_key.clone(), options);
+        let handle = self.spawn_game(game.clone());
+        games.insert(group_key, handle.clone());
+        drop(games);
+        self.stats.record(vec![StatsEvent::Asked(game.current_quiz.as_question().question_id())]);
+        handle.save().await?;
+
+        Ok(game)
+    }
+
+    /// 타이머를 멈추고 답을 받지 않는다. 설정한 시간 동안 재개하지 않으면 자동 종료
+    pub async fn pause_game(&self, group_key: &GroupKey) -> Result<()> {
+        let _lock = self.lock_group(group_key).await?;
+        self.handle(group_key).await?
+            .update(|game| {
+                if game.is_paused() {
+                    return Err(Error::GamePaused(game.group_key.clone()));
+                }
+                game.pause();
+                game.touch();
+                Ok(())
+            })
+            .await??;
+
+        Ok(())
+    }
+
+    /// 멈춘 상태 그대로 돌려준다 (문제를 다시 보여주기 위해)
+    pub async fn resume_game(&self, group_key: &GroupKey) -> Result<Game> {
+        let _lock = self.lock_group(group_key).await?;
+        self.handle(group_key).await?
+            .update(|game| {
+                if !game.is_paused() {
+                    return Err(Error::GameNotPaused(game.group_key.clone()));
+                }
+                game.resume();
+                game.touch();
+                Ok(game.clone())
+            })
+            .await?
+    }
+
+    // 그 사이에 재개했다가 다시 멈췄으면 멈춘 시간이 짧아서 넘어간다
+    async fn stop_if_paused_for(&self, group_key: &GroupKey, timeout: Duration) {
+        let Ok(_lock) = self.lock_group(group_key).await else {
+            return;
+        };
+        let expired = match self.handle(group_key).await {
+            Ok(handle) => handle
+                .call(move |game| game.paused_for().is_some_and(|paused_for| paused_for >= timeout))
+                .await
+                .unwrap_or(false),
+            Err(_) => false,
+        };
+        if !expired {
+            return;
+        }
+
+        debug!("{:<12} - stop paused game, group_key: {}", "GAME", group_key);
+        if self.stop_game_locked(group_key.clone()).await.is_ok() {
+            let message = format!("⏸️ 일시정지된 채로 {}분이 지나 게임을 종료했어요", timeout.as_secs() / 60);
+            self.announce(vec![(group_key.clone(), message)]);
+        }
+    }
+
+    /// 블리츠 시간이 다 되면 아무도 답하지 않아도 끝내고 결과를 알린다
+    async fn end_blitz(&self, group_key: &GroupKey) {
+        let Ok(_lock) = self.lock_group(group_key).await else {
+            return;
+        };
+        let finished = match self.handle(group_key).await {
+            Ok(handle) => handle.call(|game| game.is_finished()).await.unwrap_or(false),
+            Err(_) => false,
+        };
+        if !finished {
+            return;
+        }
+        let Some(game) = self.remove_finished_game(group_key).await else {
+            return;
+        };
+
+        debug!("{:<12} - blitz time over, group_key: {}", "GAME", group_key);
+        self.stats.record(vec![StatsEvent::Skipped(game.current_quiz.as_question().question_id())]);
+        let mut mentions = Mentions::default();
+        let message = game.summary().message(&mut mentions);
+        let message = format!(
+            "정답은 '{}'였어요\n{}",
+            escape_mentions(&game.current_quiz.as_question().reveal_answer()),
+            mentions.to_plain_text(&message, |user_id| self.profiles.cached_display_name(user_id)),
+        );
+        self.announce(vec![(group_key.clone(), message)]);
+    }
+
+    /// 바로 시작하지 않고 대기실을 연다. 방장이 출발하거나 카운트다운이 끝나면 시작
+    pub async fn open_lobby(&self, group_key: GroupKey, host: &str, options: StartOptions) -> Result<()> {
+        debug!("{:<12} - open_lobby, group_key: {}, options: {:?}", "GAME", group_key, options);
+        self.check_local(LOBBY_FEATURE)?;
+        options.check_difficulty()?;
+        let _lock = self.lock_group(&group_key).await?;
+        if self.games.read().await.contains_key(&group_key) {
+            return Err(Error::GameAlreadyStarted(group_key));
+        }
+
+        let lobby_id: u64 = rand::random();
+        {
+            let mut lobbies = self.lobbies.lock().await;
+            if lobbies.contains_key(&group_key) {
+                return Err(Error::LobbyAlreadyOpen(group_key));
+            }
+            lobbies.insert(group_key.clone(), Lobby {
+                id: lobby_id,
+                host: host.to_string(),
+                players: vec![host.to_string()],
+                options,
+            });
+        }
+
+        // 카운트다운이 끝날 때까지 대기실이 그대로면 시작하고 Event API로 첫 문제를 알린다
+        let gm = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(LOBBY_COUNTDOWN).await;
+            if let Ok(game) = gm.start_from_lobby(&group_key, lobby_id).await {
+                let message = format!(
+                    "⏰ 대기 시간이 끝나 {} 게임을 시작합니다! (참가 {}명)",
+                    game.mode.name(),
+                    game.roster.as_ref().map_or(0, HashSet::len),
+                );
+                gm.announce_quiz(&group_key, message, &game.current_quiz, &game.round_header());
+            }
+        });
+
+        Ok(())
+    }
+
+    /// 참가 인원을 돌려준다
+    pub async fn join_lobby(&self, group_key: &GroupKey, user_id: &str) -> Result<usize> {
+        self.check_local(LOBBY_FEATURE)?;
+        let mut lobbies = self.lobbies.lock().await;
+        let lobby = lobbies.get_mut(group_key)
+            .ok_or(Error::LobbyNotFound(group_key.clone()))?;
+
+        if !lobby.players.iter().any(|player| player == user_id) {
+            lobby.players.push(user_id.to_string());
+        }
+        Ok(lobby.players.len())
+    }
+
+    /// 방장이 "출발"하면 바로 시작한다
+    pub async fn start_lobby(&self, group_key: &GroupKey, user_id: &str) -> Result<Game> {
+        self.check_local(LOBBY_FEATURE)?;
+        let lobby_id = {
+            let lobbies = self.lobbies.lock().await;
+            let lobby = lobbies.get(group_key)
+                .ok_or(Error::LobbyNotFound(group_key.clone()))?;
+            if lobby.host != user_id {
+                return Err(Error::LobbyNotHost);
+            }
+            lobby.id
+        };
+
+        self.start_from_lobby(group_key, lobby_id).await
+    }
+
+    /// 대기실이 열려 있었으면 닫는다
+    pub async fn close_lobby(&self, group_key: &GroupKey) -> bool {
+        self.lobbies.lock().await.remove(group_key).is_some()
+    }
+
+    // 그 사이에 대기실이 닫혔다 다시 열렸으면 `lobby_id`가 달라서 시작하지 않는다
+    async fn start_from_lobby(&self, group_key: &GroupKey, lobby_id: u64) -> Result<Game> {
+        let lobby = {
+            let mut lobbies = self.lobbies.lock().await;
+            match lobbies.get(group_key) {
+                Some(lobby) if lobby.id == lobby_id => lobbies.remove(group_key).unwrap(),
+                _ => return Err(Error::LobbyNotFound(group_key.clone())),
+            }
+        };
+
+        let _lock = self.lock_group(group_key).await?;
+        let mut games = self.games.write().await;
+        if games.contains_key(group_key) {
+            return Err(Error::GameAlreadyStarted(group_key.clone()));
+        }
+
+        let mut game = Game::new(group_key.clone(), lobby.options);
+        game.set_roster(&lobby.players);
+        let handle = self.spawn_game(game.clone());
+        games.insert(group_key.clone(), handle.clone());
+        drop(games);
+        self.stats.record(vec![StatsEvent::Asked(game.current_quiz.as_question().question_id())]);
+        handle.save().await?;
+
+        Ok(game)
+    }
+
+    /// 도전장을 만들고 이 방은 바로 시작한다. 제한 시간이 지나면 결과를 알린다
+    pub async fn create_challenge(&self, group_key: GroupKey, options: StartOptions) -> Result<(String, Game)> {
+        self.check_local(CHALLENGE_FEATURE)?;
+        options.check_difficulty()?;
+        let GameMode::Rounds(rounds) = options.mode else {
+            return Err(Error::GameCommandParseFail("도전은 클래식(N문제) 모드만 가능해요"));
+        };
+        // 문제를 미리 뽑으니 적응형은 의미가 없다
+        let difficulty = match options.difficulty {
+            Some(DifficultyMode::Fixed(difficulty)) => Some(difficulty),
+            _ => None,
+        };
+
+        let _lock = self.lock_group(&group_key).await?;
+        let mut games = self.games.write().await;
+        if games.contains_key(&group_key) {
+            return Err(Error::GameAlreadyStarted(group_key));
+        }
+
+        let (code, questions) = self.challenges.create(group_key.clone(), options.category.as_deref(), difficulty, rounds).await;
+        let game = Game::new_challenge(group_key.clone(), code.clone(), questions);
+        let handle = self.spawn_game(game.clone());
+        games.insert(group_key, handle.clone());
+        drop(games);
+        self.stats.record(vec![StatsEvent::Asked(game.current_quiz.as_question().question_id())]);
+        handle.save().await?;
+
+        let gm = self.clone();
+        let expiring_code = code.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(CHALLENGE_WINDOW).await;
+            if let Some(announcements) = gm.challenges.expire(&expiring_code).await {
+                gm.announce(announcements);
+            }
+        });
+
+        Ok((code, game))
     }
 
-    pub async fn start_game(&self, group_key: GroupKey, category_name: Option<String>, is_flag_quiz: bool) -> Result<Game> {
-        debug!("{:<12} - start_game, group_key: {}, category_name: {:?}, is_flag_quiz: {}", "GAME", group_key, category_name, is_flag_quiz);
+    /// 다른 방의 도전을 받아 같은 문제로 시작한다
+    pub async fn accept_challenge(&self, group_key: GroupKey, code: &str) -> Result<Game> {
+        self.check_local(CHALLENGE_FEATURE)?;
+        let _lock = self.lock_group(&group_key).await?;
         let mut games = self.games.write().await;
-        let game = Game::new(group_key.clone(), category_name, is_flag_quiz);
+        if games.contains_key(&group_key) {
+            return Err(Error::GameAlreadyStarted(group_key));
+        }
+
+        let questions = self.challenges.accept(code, group_key.clone()).await?;
+        let game = Game::new_challenge(group_key.clone(), code.to_uppercase(), questions);
+        let handle = self.spawn_game(game.clone());
+        games.insert(group_key, handle.clone());
+        drop(games);
+        self.stats.record(vec![StatsEvent::Asked(game.current_quiz.as_question().question_id())]);
+        handle.save().await?;
+
+        Ok(game)
+    }
+
+    // 대기실과 도전은 이 인스턴스 메모리에만 있어서, 다른 인스턴스로 간 "참가", "도전 CODE"는 찾지 못한다
+    fn check_local(&self, feature: &'static str) -> Result<()> {
+        if self.distributed {
+            return Err(Error::GameNotDistributable(feature));
+        }
+        Ok(())
+    }
 
-        let prev = games.insert(group_key.clone(), Mutex::new(game.clone()));
-        match prev {
-            Some(_) => Err(Error::GameAlreadyStarted(group_key)),
-            None => Ok(game),
+    /// 게임이 (어떤 이유로든) 끝나서 목록에서 빠졌을 때.
+    /// 게임 수와 1등 횟수는 끝까지 진행된 게임만 센다
+    async fn on_game_finished(&self, game: &Game, end: GameEnd) {
+        if end == GameEnd::Finished {
+            // 한 번이라도 답한 사람은 한 게임을 한 것으로
+            let players: Vec<String> = game.participants.iter().cloned().collect();
+            let winner = game.summary().winner().map(str::to_string);
+            self.profiles.record_game(players, winner);
         }
+
+        let Some(code) = &game.challenge_code else {
+            return;
+        };
+
+        let result = RoomResult {
+            solved: game.scores.values().sum(),
+            elapsed: game.game_timer.elapsed(),
+        };
+        match self.challenges.record_result(code, &game.group_key, result).await {
+            Ok(Some(announcements)) => self.announce(announcements),
+            Ok(None) => {}
+            // 만료됐거나 재시작해서 도전이 사라졌다 (도전은 저장하지 않는다)
+            Err(e) => {
+                warn!("{:<12} - result of {} not recorded: {:?}", "GAME", game.group_key, e);
+                let message = format!("⚔️ 도전 {}이(가) 만료되어 상대 방과 결과를 비교할 수 없어요", code);
+                self.announce(vec![(game.group_key.clone(), message)]);
+            }
+        }
+    }
+
+    /// 방마다 메시지를 Event API로 보낸다
+    fn announce(&self, messages: Vec<(GroupKey, String)>) {
+        self.send_events(messages.into_iter()
+            .map(|(group_key, message)| Event::group_message(&group_key, message))
+            .collect());
+    }
+
+    /// 안내 메시지 뒤에 문제를 붙여서 보낸다. 이미지가 하나면 이미지 메시지로,
+    /// 여러 개(국기 고르기)면 보기마다 링크를 붙인다
+    fn announce_quiz(&self, group_key: &GroupKey, message: String, quiz: &QuizType, round_header: &str) {
+        let mut message = format!("{}\n{}", message, escape_mentions(&quiz.as_question().info_before(round_header)));
+        let event = match quiz.image_urls().as_slice() {
+            [] => Event::group_message(group_key, message),
+            [image_url] => Event::group_image_message(group_key, message, image_url.clone()),
+            image_urls => {
+                for (i, image_url) in image_urls.iter().enumerate() {
+                    message.push_str(format!("\n{}번: {}", i + 1, image_url).as_str());
+                }
+                Event::group_message(group_key, message)
+            }
+        };
+        self.send_events(vec![event]);
+    }
+
+    fn send_events(&self, events: Vec<Event>) {
+        let client = self.events.clone();
+        tokio::spawn(async move {
+            for event in events {
+                if let Err(e) = client.send(event.clone()).await {
+                    warn!("{:<12} - announce to {:?} failed: {:?}", "GAME", event.targets, e);
+                }
+            }
+        });
     }
 
     // fn is_valid_category(&self, category_name: &String) -> bool {
     //     QUIZ_CATEGORIES.contains(category_name)
     // }
 
-    pub async fn stop_game(&self, group_key: GroupKey) -> Result<()> {
-        let mut games = self.games.write().await;
-        games.remove(&group_key)
-            .ok_or(Error::GameNotFound(group_key))?;
+    /// 중간에 그만둔 게임의 결과를 돌려준다
+    pub async fn stop_game(&self, group_key: GroupKey) -> Result<GameSummary> {
+        let _lock = self.lock_group(&group_key).await?;
+        self.stop_game_locked(group_key).await
+    }
 
-        Ok(())
+    async fn stop_game_locked(&self, group_key: GroupKey) -> Result<GameSummary> {
+        let removed = self.games.write().await.remove(&group_key);
+        let game = removed
+            .ok_or(Error::GameNotFound(group_key.clone()))?
+            .stop()
+            .await?;
+        self.delete_snapshot(&group_key).await;
+
+        // 보고 있던 문제는 패스한 걸로
+        self.stats.record(vec![StatsEvent::Skipped(game.current_quiz.as_question().question_id())]);
+        self.on_game_finished(&game, GameEnd::Stopped).await;
+
+        Ok(game.summary())
+    }
+
+    /// 라운드는 그대로 두고 문제만 바꾼다. 도전은 문제 목록이 정해져 있어서 다음 라운드로
+    pub async fn skip_question(&self, group_key: &GroupKey) -> Result<SkipResult> {
+        let _lock = self.lock_group(group_key).await?;
+        let stats = self.stats.clone();
+        let result = self.handle(group_key).await?.update(move |game| {
+            if game.is_paused() {
+                return Err(Error::GamePaused(game.group_key.clone()));
+            }
+            game.touch();
+            game.streak = 0;
+            let skipped_quiz = game.current_quiz.clone();
+            let mut stats_events = vec![StatsEvent::Skipped(skipped_quiz.as_question().question_id())];
+
+            if game.fixed_questions.is_some() {
+                game.current_round += 1;
+            }
+            // 블리츠는 패스하는 사이에 시간이 끝날 수 있다
+            let summary = game.is_finished().then(|| game.summary());
+            if summary.is_none() {
+                game.next_quiz();
+                stats_events.push(StatsEvent::Asked(game.current_quiz.as_question().question_id()));
+            }
+            stats.record(stats_events);
+
+            let result = SkipResult {
+                skipped_quiz,
+                next_quiz: summary.is_none().then(|| game.current_quiz.clone()),
+                round_header: game.round_header(),
+                summary,
+            };
+            Ok(result)
+        }).await??;
+
+        if result.summary.is_some() {
+            self.remove_finished_game(group_key).await;
+        }
+        Ok(result)
+    }
+
+    pub async fn hint(&self, group_key: &GroupKey) -> Result<String> {
+        let _lock = self.lock_group(group_key).await?;
+        self.handle(group_key).await?.hint().await
+    }
+
+    /// 팀을 안 고르면 자동 배정. 팀전이 아니거나 이미 답해서 팀이 정해졌으면 에러
+    pub async fn join_team(&self, user_id: &str, group_key: &GroupKey, team: Option<Team>) -> Result<TeamJoinResult> {
+        let _lock = self.lock_group(group_key).await?;
+        let user_id = user_id.to_string();
+        self.handle(group_key).await?.update(move |game| {
+            game.touch();
+            let group_key = game.group_key.clone();
+            let teams = game.teams.as_mut()
+                .ok_or(Error::GameNotTeamBattle(group_key))?;
+            let team = teams.join(&user_id, team)?;
+
+            let result = TeamJoinResult {
+                team,
+                member_counts: Team::ALL.into_iter()
+                    .map(|team| (team, teams.member_count(team)))
+                    .collect(),
+            };
+            Ok(result)
+        }).await?
     }
 
     pub async fn try_answer_inmemory(&self, user_id: &str, group_key: &GroupKey, answer: &str) -> Result<AnswerResult> {
-        let games = self.games.read().await;
-        let game = games.get(group_key)
-            .ok_or(Error::GameNotFound(group_key.clone()))?;
-        
-        let mut game = game.lock().unwrap();
-        let is_correct = match &game.current_quiz {  // 으아악
-            QuizType::Simple(quiz) => quiz.is_correct_answer(answer),
-            QuizType::Flag(flag_quiz) => flag_quiz.is_correct_answer(answer),
+        let _lock = self.lock_group(group_key).await?;
+        let handle = self.handle(group_key).await?;
+        let judgement = {
+            let stats = self.stats.clone();
+            let distributed = self.distributed;
+            let (user_id, answer) = (user_id.to_string(), answer.to_string());
+            handle.update(move |game| {
+                game.touch();
+                match game.judge_answer(&stats, &user_id, &answer) {
+                    // 한 인스턴스면 게임 task가 답을 하나씩 처리하니 먼저 온 답이 바로 차지한다
+                    Judgement::Correct { .. } if !distributed => Judgement::Decided(Box::new(game.score_answer(&stats, &user_id))),
+                    judgement => judgement,
+                }
+            }).await?
         };
-        if !is_correct {
-            return Ok(AnswerResult::Wrong);
+
+        // 여러 인스턴스면 맞혔어도 이 문제를 저장소에서 먼저 차지해야 점수를 얻는다
+        let mut result = match judgement {
+            Judgement::Decided(result) => *result,
+            Judgement::Correct { round_id } => match self.store.claim_round(group_key, round_id, user_id).await? {
+                RoundClaim::Lost { winner } => AnswerResult::Late { winner: Some(winner) },
+                RoundClaim::Won => {
+                    let stats = self.stats.clone();
+                    let winner = user_id.to_string();
+                    handle.update(move |game| {
+                        // 차지하는 사이에 패스해서 문제가 바뀌었으면
+                        if game.round_id != round_id {
+                            AnswerResult::Late { winner: None }
+                        } else {
+                            game.score_answer(&stats, &winner)
+                        }
+                    }).await?
+                }
+            },
+        };
+        if let AnswerResult::Correct { user_id, score, current_quiz, answer_time, streak, .. } = &mut result {
+            let correct = CorrectAnswer {
+                user_id,
+                group_key,
+                category: current_quiz.category(),
+                answer_time: *answer_time,
+                streak: *streak,
+            };
+            *score = self.profiles.add_correct(correct).await
+                .map_err(|e| warn!("{:<12} - record correct failed: {:?}", "GAME", e))
+                .ok();
+            debug!("{:<12} - correct, group_key: {}, user: {}", "GAME", group_key, self.profiles.label(user_id));
         }
-        
-        // scores
-        let mut scores_by_user = SCORES_BY_USER.lock().unwrap();
-        let mut scores_by_group = SCORES_BY_GROUP.lock().unwrap();
-        
-        *scores_by_user.entry(user_id.to_string()).or_insert(0) += 1;
-        *scores_by_group.entry(group_key.clone()).or_insert(0) += 1;
 
-        let current_quiz = game.current_quiz.clone();
-        
-        game.current_round += 1;
-        // category에 따라. 없는 카테고리면 랜덤하게
-        // TODO: 한쪽으로 정리. 시작할 떄?
-        if let Some(category) = &game.selected_category {
-            // game.current_quiz = quiz_db().get_random_quiz_by_category(category)
-            //     .unwrap_or(quiz_db().get_any_random_quiz());
-            
-            // 국기인 경우에는 국기 문제만
-            if category == "국기" {
-                game.current_quiz = QuizType::Flag(flag_quiz_db().get_random_flag_quiz().clone());
-            } else {
-                let next_quiz = quiz_db().get_random_quiz_by_category(category)
-                    .unwrap_or(quiz_db().get_any_random_quiz());
-
-                game.current_quiz = QuizType::Simple(next_quiz.clone());
-            }
-        } else {
-            game.current_quiz = QuizType::Simple(quiz_db().get_any_random_quiz().clone());
+        // 끝난 게임은 정리
+        if result.summary().is_some() {
+            self.remove_finished_game(group_key).await;
         }
+        Ok(result)
+    }
 
-        // TODO: rank
-        
-        Ok(AnswerResult::Correct {
-            user_id: user_id.to_string(),
-            score: *scores_by_user.get(user_id).unwrap(),
-            current_quiz,
-            next_quiz: game.current_quiz.clone(),
-            current_round: game.current_round,
-        })
+    // 앞에 들어온 명령(과 저장)이 모두 끝난 뒤에 지워야 다시 살아나지 않는다
+    async fn remove_finished_game(&self, group_key: &GroupKey) -> Option<Game> {
+        let removed = self.games.write().await.remove(group_key)?;
+        let game = removed.stop().await.ok()?;
+        self.delete_snapshot(group_key).await;
+        self.on_game_finished(&game, GameEnd::Finished).await;
+        Some(game)
     }
-    
+
     // region: redis (TODO)
     // // TODO: race cond?
     // pub async fn try_answer_with_redis(&self, user_id: String, group_key: GroupKey, answer: String) -> Result<AnswerResult> {
@@ -185,21 +789,9 @@ impl GameManager {
     // }
     // endregion: redis
 
-    // Return user ranking and group ranking, using ZRANK
-    // TODO
-    pub async fn get_ranking(&self, user_id: &str, group_key: &str) -> Result<(i32, i32)> {
-        // let mut conn = self.pool.get()
-        //     .await
-        //     .map_err(|_| Error::RedisConnectionGetFail)?;
-        // 
-        // let user_rank = conn.zrank(REDIS_USER_SCORES_KEY, user_id).await
-        //     .map_err(|_| Error::RedisCommandFail(REDIS_USER_SCORES_KEY.to_string()))?;
-        // let group_rank = conn.zrank(REDIS_GROUP_SCORES_KEY, group_key).await
-        //     .map_err(|_| Error::RedisCommandFail(REDIS_GROUP_SCORES_KEY.to_string()))?;
-        // 
-        // Ok((user_rank, group_rank))
-        
-        Ok((123, 123))
+    // Return user ranking and group ranking, using ZREVRANK (1등부터). 맞힌 적이 없으면 None
+    pub async fn get_ranking(&self, user_id: &str, group_key: &GroupKey) -> Result<(Option<usize>, Option<usize>)> {
+        self.profiles.ranks(user_id, group_key).await
     }
 }
 
@@ -209,40 +801,1071 @@ impl GameManager {
 // pub const MAX_ROUNDS: u8 = 10;
 pub const MAX_ROUNDS: u8 = 3;
 
-#[derive(Clone)]
+// 적응형 난이도: 이보다 빨리 맞히면 어렵게, 늦게 맞히면 쉽게
+const ADAPTIVE_FAST_ANSWER: Duration = Duration::from_secs(10);
+const ADAPTIVE_SLOW_ANSWER: Duration = Duration::from_secs(40);
+
+// 누가 맞힌 뒤 이 시간 안에 같은 답을 보내면 "아깝게 늦었어요"
+const LATE_ANSWER_WINDOW: Duration = Duration::from_secs(5);
+
+const IDLE_GAME_REAP_INTERVAL: Duration = Duration::from_secs(60);
+
+// 대기실을 열고 이 시간이 지나면 자동으로 시작
+const LOBBY_COUNTDOWN: Duration = Duration::from_secs(60);
+
+// 여러 인스턴스일 때 막는 기능. 안내 메시지에 쓴다
+const LOBBY_FEATURE: &str = "대기실";
+const CHALLENGE_FEATURE: &str = "방 대 방 도전";
+
+/// 게임이 어떻게 끝났는지
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GameEnd {
+    // 문제를 다 냈거나 목표 점수, 블리츠 시간 종료 등 모드대로 끝났다
+    Finished,
+    // 중지, 일시정지 시간 초과, 오래 방치돼서 자동 종료
+    Stopped,
+}
+
+/// `시작 [카테고리] [난이도] [모드] [팀전] [대기실]`
+#[derive(Debug, Clone, Default)]
+pub struct StartOptions {
+    pub category: Option<String>,  // 없으면 all random
+    pub difficulty: Option<DifficultyMode>,  // 없으면 난이도 구분 없이
+    pub mode: GameMode,
+    pub team_battle: bool,
+    pub lobby: bool,
+}
+
+impl StartOptions {
+    /// 국기, 이미지, 초성 등은 난이도 데이터가 없어서 난이도를 고를 수 없다
+    pub fn check_difficulty(&self) -> Result<()> {
+        if self.difficulty.is_some() && !supports_difficulty(self.category.as_deref()) {
+            return Err(Error::GameDifficultyNotSupported(self.category.clone().unwrap_or_default()));
+        }
+        Ok(())
+    }
+}
+
+/// 방금 누가 맞힌 문제
+#[derive(Clone, Serialize, Deserialize)]
+struct ClaimedRound {
+    quiz: QuizType,
+    winner: String,
+    claimed: Stopwatch,
+}
+
+/// `Game::judge_answer`
+enum Judgement {
+    Decided(Box<AnswerResult>),
+    // 맞혔지만 아직 점수를 주기 전
+    Correct { round_id: u64 },
+}
+
+/// `GameManager::lock_group`. 드롭하면 푼다
+struct GroupLock {
+    store: Arc<dyn GameStore>,
+    group_key: GroupKey,
+    // 한 인스턴스일 때는 None (잠그지 않음)
+    token: Option<String>,
+}
+
+impl Drop for GroupLock {
+    fn drop(&mut self) {
+        let Some(token) = self.token.take() else {
+            return;
+        };
+        let store = self.store.clone();
+        let group_key = std::mem::take(&mut self.group_key);
+        tokio::spawn(async move {
+            if let Err(e) = store.unlock(&group_key, &token).await {
+                warn!("{:<12} - unlock {} failed: {:?}", "GAME", group_key, e);
+            }
+        });
+    }
+}
+
+/// 시작 전 참가자를 모으는 중
+struct Lobby {
+    id: u64,
+    host: String,
+    // 참가 순서대로
+    players: Vec<String>,
+    options: StartOptions,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Game {
     group_key: GroupKey,
     pub current_round: u8,
+    // 문제마다 바뀐다. 같은 문제를 두 사람이 맞히지 않도록 이걸로 차지한다
+    round_id: u64,
+    last_round: Option<ClaimedRound>,
+    // `last_round`를 맞힌 사람이 연속으로 맞힌 문제 수. 패스하면 끊긴다
+    #[serde(default)]
+    streak: u32,
+    // 저장할 때마다 오른다. 저장소는 이보다 낮은(오래된) 게임을 받지 않는다
+    #[serde(default)]
+    pub version: u64,
+    // 지금 문제에서 보여준 힌트 수
+    #[serde(default)]
+    hints: u8,
     // pub current_quiz: &'static Quiz,
     pub current_quiz: QuizType,
     pub selected_category: Option<String>,  // 없으면 all random
+    pub difficulty_mode: Option<DifficultyMode>,
+    // 다음 문제를 고를 때 쓰는 난이도. 적응형이면 라운드마다 바뀐다
+    pub current_difficulty: Option<Difficulty>,
+    round_timer: Stopwatch,
+
+    pub mode: GameMode,
+    game_timer: Stopwatch,
+    // 일시정지 중이면 멈춘 뒤로 흐른 시간
+    pause_timer: Option<Stopwatch>,
+    // 마지막 활동(답, 패스 등) 뒤로 흐른 시간
+    idle_timer: Stopwatch,
+    // 이번 게임 점수 (user_id -> score)
+    pub scores: HashMap<String, u32>,
+    // 한 번이라도 답한 사람 / 서바이벌에서 탈락한 사람
+    participants: HashSet<String>,
+    eliminated: HashSet<String>,
+    // 팀전일 때만. 게임과 함께 사라진다
+    pub teams: Option<Teams>,
+
+    // 도전일 때만. 정해진 문제를 순서대로 낸다
+    pub challenge_code: Option<String>,
+    fixed_questions: Option<Arc<Vec<QuizType>>>,
+
+    // 대기실을 거쳐 시작했을 때 참가자
+    pub roster: Option<HashSet<String>>,
 }
 
 impl Game {
-    pub fn new(group_key: GroupKey, selected_category: Option<String>, is_flag_quiz: bool) -> Self {
+    pub fn new(group_key: GroupKey, options: StartOptions) -> Self {
+        let current_quiz = get_random_quiz(options.category.as_deref(), Self::starting_difficulty(&options));
+        Self::with_first_quiz(group_key, options, current_quiz)
+    }
+
+    fn starting_difficulty(options: &StartOptions) -> Option<Difficulty> {
+        match options.difficulty {
+            Some(DifficultyMode::Fixed(difficulty)) => Some(difficulty),
+            Some(DifficultyMode::Adaptive) => Some(Difficulty::default()),
+            None => None,
+        }
+    }
+
+    fn with_first_quiz(group_key: GroupKey, options: StartOptions, current_quiz: QuizType) -> Self {
         Self {
             group_key,
             current_round: 1,
-            current_quiz: if is_flag_quiz {
-                QuizType::Flag(flag_quiz_db().get_random_flag_quiz().clone())
-            } else {
-                QuizType::Simple(quiz_db().get_any_random_quiz().clone())
-            },
-            selected_category,
+            // 같은 방에서 새로 시작한 게임과 겹치지 않도록
+            round_id: rand::random(),
+            last_round: None,
+            streak: 0,
+            version: 0,
+            hints: 0,
+            current_quiz,
+            current_difficulty: Self::starting_difficulty(&options),
+            selected_category: options.category,
+            difficulty_mode: options.difficulty,
+            round_timer: Stopwatch::start(),
+            mode: options.mode,
+            game_timer: Stopwatch::start(),
+            pause_timer: None,
+            idle_timer: Stopwatch::start(),
+            scores: HashMap::new(),
+            participants: HashSet::new(),
+            eliminated: HashSet::new(),
+            teams: options.team_battle.then(Teams::default),
+            challenge_code: None,
+            fixed_questions: None,
+            roster: None,
+        }
+    }
+
+    /// 대기실 참가자로 시작. 팀전이면 참가 순서대로 번갈아 팀을 나눈다
+    fn set_roster(&mut self, players: &[String]) {
+        if let Some(teams) = self.teams.as_mut() {
+            // 아직 아무도 답하지 않아서 실패하지 않는다
+            for player in players {
+                let _ = teams.join(player, None);
+            }
+        }
+        // 서바이벌은 답을 안 한 참가자도 생존자로
+        self.participants.extend(players.iter().cloned());
+        self.roster = Some(players.iter().cloned().collect());
+    }
+
+    /// 참가자 명단이 필요한 모드(서바이벌, 팀전)에서는 대기실에서 참가한 사람만 점수를 얻는다
+    fn can_score(&self, user_id: &str) -> bool {
+        let needs_roster = self.mode == GameMode::Survival || self.teams.is_some();
+        match &self.roster {
+            Some(roster) if needs_roster => roster.contains(user_id),
+            _ => true,
+        }
+    }
+
+    /// 도전 게임. 문제 수만큼의 클래식 모드
+    pub fn new_challenge(group_key: GroupKey, code: String, questions: Arc<Vec<QuizType>>) -> Self {
+        let options = StartOptions {
+            mode: GameMode::Rounds(questions.len() as u8),
+            ..StartOptions::default()
+        };
+
+        // 첫 문제도 정해진 문제로 (따로 뽑지 않는다)
+        Self {
+            challenge_code: Some(code),
+            fixed_questions: Some(questions.clone()),
+            ..Self::with_first_quiz(group_key, options, questions[0].clone())
+        }
+    }
+
+    fn touch(&mut self) {
+        self.idle_timer = Stopwatch::start();
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        self.idle_timer.elapsed()
+    }
+
+    pub fn group_key(&self) -> &GroupKey {
+        &self.group_key
+    }
+
+    /// 블리츠면 남은 시간
+    pub fn blitz_remaining(&self) -> Option<Duration> {
+        match self.mode {
+            GameMode::Blitz(duration) => Some(duration.saturating_sub(self.game_timer.elapsed())),
+            _ => None,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause_timer.is_some()
+    }
+
+    /// 일시정지 중이면 멈춘 뒤로 흐른 시간
+    pub fn paused_for(&self) -> Option<Duration> {
+        self.pause_timer.as_ref().map(Stopwatch::elapsed)
+    }
+
+    /// 라운드/게임 시간을 멈춘다
+    fn pause(&mut self) {
+        self.round_timer.pause();
+        self.game_timer.pause();
+        self.pause_timer = Some(Stopwatch::start());
+    }
+
+    fn resume(&mut self) {
+        self.round_timer.resume();
+        self.game_timer.resume();
+        self.pause_timer = None;
+    }
+
+    pub fn round_header(&self) -> String {
+        self.mode.round_header(self.current_round, self.game_timer.elapsed())
+    }
+
+    // category에 따라. 없는 카테고리면 랜덤하게
+    fn next_quiz(&mut self) {
+        self.current_quiz = match &self.fixed_questions {
+            Some(questions) => questions[(self.current_round as usize - 1).min(questions.len() - 1)].clone(),
+            None => get_random_quiz(self.selected_category.as_deref(), self.current_difficulty),
+        };
+        self.round_id = self.round_id.wrapping_add(1);
+        self.round_timer = Stopwatch::start();
+        self.hints = 0;
+    }
+
+    /// 지금 문제의 다음 힌트. 부를 때마다 한 글자씩 더 보여준다
+    pub fn next_hint(&mut self) -> Result<String> {
+        if self.is_paused() {
+            return Err(Error::GamePaused(self.group_key.clone()));
+        }
+        let hint = self.current_quiz.hint(self.hints as usize + 1)
+            .ok_or(Error::GameNoHint(self.group_key.clone()))?;
+        self.hints = self.hints.saturating_add(1);
+        self.touch();
+        Ok(hint)
+    }
+
+    fn survivors(&self) -> impl Iterator<Item = &String> {
+        self.participants.difference(&self.eliminated)
+    }
+
+    /// 모드별 종료 조건
+    pub fn is_finished(&self) -> bool {
+        match self.mode {
+            GameMode::Rounds(rounds) => self.current_round > rounds,
+            GameMode::FirstTo(target) => self.scores.values().any(|score| *score >= target),
+            GameMode::Survival => {
+                let survivors = self.survivors().count();
+                // 혼자 하면 틀릴 때까지
+                (self.participants.len() >= 2 && survivors <= 1)
+                    || (!self.participants.is_empty() && survivors == 0)
+                    || self.current_round > SURVIVAL_MAX_ROUNDS
+            }
+            GameMode::Blitz(duration) => self.game_timer.elapsed() >= duration,
+        }
+    }
+
+    pub fn summary(&self) -> GameSummary {
+        let survivors: HashSet<&String> = self.survivors().collect();
+
+        let mut rankings: Vec<(String, u32)> = self.scores.iter()
+            .map(|(user_id, score)| (user_id.clone(), *score))
+            .collect();
+        if self.mode == GameMode::Survival {
+            // 점수 없는 생존자도 순위에
+            for user_id in &survivors {
+                if !self.scores.contains_key(*user_id) {
+                    rankings.push(((*user_id).clone(), 0));
+                }
+            }
+        }
+        rankings.sort_by(|(a_user, a_score), (b_user, b_score)| {
+            let survived = |user_id: &String| self.mode == GameMode::Survival && survivors.contains(user_id);
+            survived(b_user).cmp(&survived(a_user))
+                .then(b_score.cmp(a_score))
+                .then(a_user.cmp(b_user))
+        });
+
+        GameSummary {
+            mode: self.mode,
+            rounds_played: self.current_round.saturating_sub(1),
+            rankings,
+            survivors: survivors.into_iter().cloned().collect(),
+            teams: self.teams.as_ref().map(Teams::summary),
+            challenge_code: self.challenge_code.clone(),
+        }
+    }
+
+    /// 점수는 주지 않고 판정만. 맞혔으면 지금 문제의 `round_id`
+    fn judge_answer(&mut self, stats: &QuestionStatsStore, user_id: &str, answer: &str) -> Judgement {
+        if self.is_paused() {
+            return Judgement::Decided(Box::new(AnswerResult::Paused));
+        }
+        // 블리츠: 시간이 지났으면 답과 상관없이 종료
+        if self.is_finished() {
+            stats.record(vec![StatsEvent::Skipped(self.current_quiz.as_question().question_id())]);
+            return Judgement::Decided(Box::new(AnswerResult::TimeOver(self.summary())));
+        }
+        if self.eliminated.contains(user_id) {
+            return Judgement::Decided(Box::new(AnswerResult::AlreadyEliminated));
+        }
+        if !self.can_score(user_id) {
+            return Judgement::Decided(Box::new(AnswerResult::NotJoined));
+        }
+        self.participants.insert(user_id.to_string());
+        if let Some(teams) = self.teams.as_mut() {
+            teams.ensure_member(user_id);
+        }
+
+        if self.current_quiz.is_correct_answer(answer) {
+            return Judgement::Correct { round_id: self.round_id };
+        }
+
+        // 방금 다른 사람이 맞힌 문제의 답이면 틀린 게 아니라 늦은 것
+        let late_winner = self.last_round.as_ref()
+            .filter(|last| last.claimed.elapsed() < LATE_ANSWER_WINDOW && last.winner != user_id)
+            .filter(|last| last.quiz.is_correct_answer(answer))
+            .map(|last| last.winner.clone());
+        if let Some(winner) = late_winner {
+            return Judgement::Decided(Box::new(AnswerResult::Late { winner: Some(winner) }));
+        }
+
+        if self.mode != GameMode::Survival {
+            return Judgement::Decided(Box::new(AnswerResult::Wrong { eliminated: false, summary: None }));
+        }
+
+        self.eliminated.insert(user_id.to_string());
+        let summary = self.is_finished().then(|| {
+            stats.record(vec![StatsEvent::Skipped(self.current_quiz.as_question().question_id())]);
+            self.summary()
+        });
+        Judgement::Decided(Box::new(AnswerResult::Wrong { eliminated: true, summary }))
+    }
+
+    /// 이 문제를 차지한 사람에게 점수를 주고 다음 문제로
+    fn score_answer(&mut self, stats: &QuestionStatsStore, user_id: &str) -> AnswerResult {
+        let team = self.teams.as_mut().map(|teams| teams.ensure_member(user_id));
+
+        // scores (누적 점수는 게임 밖에서)
+        *self.scores.entry(user_id.to_string()).or_insert(0) += 1;
+        if let (Some(teams), Some(team)) = (self.teams.as_mut(), team) {
+            teams.add_point(team);
+        }
+
+        let current_quiz = self.current_quiz.clone();
+        let answer_time = self.round_timer.elapsed();
+        let mut stats_events = vec![StatsEvent::Solved(current_quiz.as_question().question_id(), answer_time)];
+        self.streak = match &self.last_round {
+            Some(last_round) if last_round.winner == user_id && self.streak > 0 => self.streak + 1,
+            _ => 1,
+        };
+        self.last_round = Some(ClaimedRound {
+            quiz: current_quiz.clone(),
+            winner: user_id.to_string(),
+            claimed: Stopwatch::start(),
+        });
+        let difficulty_changed = self.adjust_difficulty();
+        
+        self.current_round += 1;
+        let summary = self.is_finished().then(|| self.summary());
+        if summary.is_none() {
+            self.next_quiz();
+            stats_events.push(StatsEvent::Asked(self.current_quiz.as_question().question_id()));
+        }
+        stats.record(stats_events);
+
+        // TODO: rank
+        
+        AnswerResult::Correct {
+            user_id: user_id.to_string(),
+            // `GameManager`가 채운다
+            score: None,
+            game_score: self.scores[user_id],
+            team,
+            current_quiz: Box::new(current_quiz),
+            answer_time,
+            streak: self.streak,
+            next_quiz: summary.is_none().then(|| Box::new(self.current_quiz.clone())),
+            round_header: self.round_header(),
+            difficulty_changed,
+            summary,
         }
     }
+
+    /// 적응형일 때 이번 라운드를 맞힌 시간으로 다음 난이도를 정한다. 바뀌었으면 새 난이도
+    fn adjust_difficulty(&mut self) -> Option<Difficulty> {
+        if self.difficulty_mode != Some(DifficultyMode::Adaptive) {
+            return None;
+        }
+
+        let current = self.current_difficulty.unwrap_or_default();
+        let elapsed = self.round_timer.elapsed();
+        let next = if elapsed < ADAPTIVE_FAST_ANSWER {
+            current.harder()
+        } else if elapsed > ADAPTIVE_SLOW_ANSWER {
+            current.easier()
+        } else {
+            current
+        };
+        self.current_difficulty = Some(next);
+
+        (next != current).then_some(next)
+    }
 }
 
 pub enum AnswerResult {
     Correct {
         user_id: String,
         // NOTE: redis integer is i64, but for now it's enough to use u32
-        score: u32,
+        // 누적 점수. 기록하지 못했으면 None
+        score: Option<u32>,
+        // 이번 게임 점수
+        game_score: u32,
+        // 팀전이면 점수를 얻은 팀
+        team: Option<Team>,
         // current_quiz: &'static Quiz,
-        current_quiz: QuizType,
+        current_quiz: Box<QuizType>,
+        // 문제가 나오고 맞힐 때까지 걸린 시간
+        answer_time: Duration,
+        // 이 사람이 연속으로 맞힌 문제 수 (이번 문제 포함)
+        streak: u32,
         // next_quiz: &'static Quiz,
-        next_quiz: QuizType,
-        current_round: u8,
+        // 게임이 끝났으면 None
+        next_quiz: Option<Box<QuizType>>,
+        round_header: String,
+        // 적응형 난이도가 바뀌었으면 새 난이도
+        difficulty_changed: Option<Difficulty>,
+        summary: Option<GameSummary>,
+    },
+    Wrong {
+        // 서바이벌에서 이번에 탈락
+        eliminated: bool,
+        summary: Option<GameSummary>,
     },
-    Wrong,
+    // 서바이벌에서 이미 탈락한 사람
+    AlreadyEliminated,
+    // 대기실에서 참가하지 않은 사람
+    NotJoined,
+    // 일시정지 중
+    Paused,
+    // 맞혔지만 다른 사람이 먼저. 문제가 패스됐으면 None
+    Late {
+        winner: Option<String>,
+    },
+    // 블리츠 시간 초과
+    TimeOver(GameSummary),
+}
+
+impl AnswerResult {
+    /// 이번 답으로 게임이 끝났으면 결과
+    pub fn summary(&self) -> Option<&GameSummary> {
+        match self {
+            AnswerResult::Correct { summary, .. } | AnswerResult::Wrong { summary, .. } => summary.as_ref(),
+            AnswerResult::AlreadyEliminated | AnswerResult::NotJoined | AnswerResult::Paused | AnswerResult::Late { .. } => None,
+            AnswerResult::TimeOver(summary) => Some(summary),
+        }
+    }
+}
+
+pub struct SkipResult {
+    pub skipped_quiz: QuizType,
+    // 게임이 끝났으면 None
+    pub next_quiz: Option<QuizType>,
+    pub round_header: String,
+    pub summary: Option<GameSummary>,
+}
+
+pub struct TeamJoinResult {
+    pub team: Team,
+    pub member_counts: Vec<(Team, usize)>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameManagerStatus {
+    pub active_games: usize,
+    pub open_lobbies: usize,
+    pub evicted_games: u64,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::future::Future;
+
+    use bb8::Pool;
+    use bb8_redis::RedisConnectionManager;
+
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::event::{EventTarget, LogEventClient, RecordingEventClient, MESSAGE_PARAM};
+    use crate::game::daily::InMemoryDailyStore;
+    use crate::game::db::MATH_CATEGORY;
+    use crate::game::score::InMemoryScoreStore;
+    use crate::game::store::InMemoryGameStore;
+
+    // Redis 없이. 저장소 밖의 기록(통계 등)은 백그라운드에서 실패하고 끝난다
+    pub(crate) fn test_manager(store: Arc<dyn GameStore>, distributed: bool) -> GameManager {
+        test_manager_with_events(store, distributed, Arc::new(LogEventClient))
+    }
+
+    pub(crate) fn test_manager_with_events(store: Arc<dyn GameStore>, distributed: bool, events: Arc<dyn EventClient>) -> GameManager {
+        test_manager_with(store, Arc::new(InMemoryScoreStore::default()), distributed, events)
+    }
+
+    pub(crate) fn test_manager_with(store: Arc<dyn GameStore>, scores: Arc<dyn ScoreStore>, distributed: bool, events: Arc<dyn EventClient>) -> GameManager {
+        let manager = RedisConnectionManager::new("redis://127.0.0.1:1").unwrap();
+        let pool = Pool::builder().build_unchecked(manager);
+        GameManager::new(pool, events, Arc::new(SystemClock), store, scores, Arc::new(InMemoryDailyStore::default()), distributed).unwrap()
+    }
+
+    // 조건이 맞을 때까지 기다린다 (백그라운드 타이머, Event API 전송 등)
+    pub(crate) async fn wait_until<F: Future<Output = bool>>(mut done: impl FnMut() -> F) {
+        for _ in 0..200 {
+            if done().await {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("timed out");
+    }
+
+    // 문제 db 파일 없이 만들 수 있는 수학 문제로
+    pub(crate) fn math_options() -> StartOptions {
+        StartOptions {
+            category: Some(MATH_CATEGORY.to_string()),
+            ..StartOptions::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn restore_games_from_store() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::default());
+        let group_key = "room".to_string();
+        let gm = test_manager(store.clone(), false);
+        gm.start_game(group_key.clone(), math_options()).await.unwrap();
+        gm.pause_game(&group_key).await.unwrap();
+
+        // 재시작
+        let restarted = test_manager(store.clone(), false);
+        restarted.restore_games().await.unwrap();
+        assert_eq!(restarted.status().await.active_games, 1);
+
+        let game = restarted.resume_game(&group_key).await.unwrap();
+        assert!(!game.is_paused());
+        assert_eq!(store.load(&group_key).await.unwrap().unwrap().version, 3);
+
+        restarted.stop_game(group_key.clone()).await.unwrap();
+        assert!(store.load(&group_key).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn starting_twice_keeps_the_running_game() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::default());
+        let group_key = "room".to_string();
+        let gm = test_manager(store.clone(), false);
+        let game = gm.start_game(group_key.clone(), math_options()).await.unwrap();
+        gm.try_answer_inmemory("alice", &group_key, &math_answer(&game)).await.unwrap();
+
+        assert!(matches!(gm.start_game(group_key.clone(), math_options()).await, Err(Error::GameAlreadyStarted(_))));
+
+        // 돌던 게임의 라운드와 점수가 그대로다
+        let running = gm.handle(&group_key).await.unwrap().call(|game| game.clone()).await.unwrap();
+        assert_eq!(running.current_round, 2);
+        assert_eq!(running.scores["alice"], 1);
+        let saved = store.load(&group_key).await.unwrap().unwrap();
+        assert_eq!(saved.current_round, 2);
+        assert_eq!(saved.scores["alice"], 1);
+    }
+
+    #[tokio::test]
+    async fn restored_game_paused_too_long_is_stopped() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::default());
+        let group_key = "room".to_string();
+        let mut game = Game::new(group_key.clone(), math_options());
+        game.pause();
+        // 멈춘 채로 내려가 있던 사이에 시간이 다 됐다
+        let mut json = serde_json::to_value(&game).unwrap();
+        json["pause_timer"]["elapsed_ms"] = serde_json::json!(config().PAUSED_GAME_TIMEOUT.as_millis() as u64);
+        let mut game: Game = serde_json::from_value(json).unwrap();
+        game.version = 1;
+        store.save(&game).await.unwrap();
+
+        let gm = test_manager(store.clone(), false);
+        gm.restore_games().await.unwrap();
+        wait_until(|| async { gm.status().await.active_games == 0 }).await;
+        assert_eq!(gm.status().await.active_games, 0);
+        assert!(store.load(&group_key).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn blitz_ends_when_time_runs_out() {
+        let events = RecordingEventClient::default();
+        let gm = test_manager_with_events(Arc::new(InMemoryGameStore::default()), false, Arc::new(events.clone()));
+        let group_key = "room".to_string();
+        let options = StartOptions {
+            mode: GameMode::Blitz(Duration::from_millis(200)),
+            ..math_options()
+        };
+        gm.start_game(group_key.clone(), options).await.unwrap();
+
+        // 아무도 답하지 않아도 끝나고 알린다
+        wait_until(|| async { !events.sent().is_empty() }).await;
+        assert_eq!(gm.status().await.active_games, 0);
+        let sent = events.sent();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].params[MESSAGE_PARAM].contains("시간 종료"));
+    }
+
+    #[tokio::test]
+    async fn paused_blitz_does_not_end() {
+        let events = RecordingEventClient::default();
+        let gm = test_manager_with_events(Arc::new(InMemoryGameStore::default()), false, Arc::new(events.clone()));
+        let group_key = "room".to_string();
+        let options = StartOptions {
+            mode: GameMode::Blitz(Duration::from_millis(200)),
+            ..math_options()
+        };
+        gm.start_game(group_key.clone(), options).await.unwrap();
+        gm.pause_game(&group_key).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(gm.status().await.active_games, 1);
+        assert!(events.sent().is_empty());
+    }
+
+    #[tokio::test]
+    async fn first_to_ends_at_the_target_score() {
+        let gm = test_manager(Arc::new(InMemoryGameStore::default()), false);
+        let group_key = "room".to_string();
+        let options = StartOptions {
+            mode: GameMode::FirstTo(2),
+            ..math_options()
+        };
+        let game = gm.start_game(group_key.clone(), options).await.unwrap();
+
+        let AnswerResult::Correct { next_quiz, summary: None, .. } = gm.try_answer_inmemory("alice", &group_key, &math_answer(&game)).await.unwrap() else {
+            panic!("ended too early");
+        };
+        let QuizType::Math(quiz) = *next_quiz.unwrap() else {
+            unreachable!();
+        };
+        gm.try_answer_inmemory("bob", &group_key, &quiz.answer.to_string()).await.unwrap();
+        let game = gm.handle(&group_key).await.unwrap().call(|game| game.clone()).await.unwrap();
+
+        let AnswerResult::Correct { next_quiz: None, summary: Some(summary), .. } = gm.try_answer_inmemory("alice", &group_key, &math_answer(&game)).await.unwrap() else {
+            panic!("not ended");
+        };
+        assert_eq!(summary.winner(), Some("alice"));
+        assert_eq!(gm.status().await.active_games, 0);
+    }
+
+    #[tokio::test]
+    async fn survival_ends_with_one_survivor() {
+        let gm = test_manager(Arc::new(InMemoryGameStore::default()), false);
+        let group_key = "room".to_string();
+        let options = StartOptions {
+            mode: GameMode::Survival,
+            ..math_options()
+        };
+        let game = gm.start_game(group_key.clone(), options).await.unwrap();
+
+        // 혼자 남아도 다른 참가자가 없으면 계속
+        let AnswerResult::Correct { summary: None, .. } = gm.try_answer_inmemory("alice", &group_key, &math_answer(&game)).await.unwrap() else {
+            panic!("ended too early");
+        };
+        let AnswerResult::Wrong { eliminated: true, summary: Some(summary) } = gm.try_answer_inmemory("bob", &group_key, "오답").await.unwrap() else {
+            panic!("not ended");
+        };
+        assert_eq!(summary.survivors, vec!["alice".to_string()]);
+        assert_eq!(summary.winner(), Some("alice"));
+        assert!(matches!(gm.try_answer_inmemory("bob", &group_key, "오답").await, Err(Error::GameNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn reaper_evicts_idle_games() {
+        let gm = test_manager(Arc::new(InMemoryGameStore::default()), false);
+        for i in 0..3 {
+            gm.start_game(format!("room{}", i), math_options()).await.unwrap();
+        }
+
+        gm.reap_idle_games(Duration::from_secs(60)).await;
+        assert_eq!(gm.status().await.active_games, 3);
+
+        gm.reap_idle_games(Duration::ZERO).await;
+        let status = gm.status().await;
+        assert_eq!(status.active_games, 0);
+        assert_eq!(status.evicted_games, 3);
+    }
+
+    #[tokio::test]
+    async fn evicted_game_is_announced() {
+        let events = RecordingEventClient::default();
+        let gm = test_manager_with_events(Arc::new(InMemoryGameStore::default()), false, Arc::new(events.clone()));
+        gm.start_game("room".to_string(), math_options()).await.unwrap();
+
+        gm.reap_idle_games(Duration::ZERO).await;
+        wait_until(|| async { !events.sent().is_empty() }).await;
+        let sent = events.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].name, crate::event::MESSAGE_EVENT_NAME);
+        assert!(matches!(&sent[0].targets[..], [EventTarget::BotGroupKey(group_key)] if group_key == "room"));
+        assert!(sent[0].params[MESSAGE_PARAM].contains("자동 종료"));
+    }
+
+    #[tokio::test]
+    async fn hints_reveal_one_more_char() {
+        let gm = test_manager(Arc::new(InMemoryGameStore::default()), false);
+        let group_key = "room".to_string();
+        gm.start_game(group_key.clone(), math_options()).await.unwrap();
+
+        let first = gm.hint(&group_key).await.unwrap();
+        assert!(first.chars().all(|c| c == '○' || !c.is_ascii_digit()));
+        gm.pause_game(&group_key).await.unwrap();
+        assert!(matches!(gm.hint(&group_key).await, Err(Error::GamePaused(_))));
+    }
+
+    // 이 방만 저장이 느리다
+    struct SlowRoomStore {
+        inner: InMemoryGameStore,
+        slow_room: GroupKey,
+        delay: Duration,
+    }
+
+    #[async_trait::async_trait]
+    impl GameStore for SlowRoomStore {
+        async fn save(&self, game: &Game) -> Result<()> {
+            if *game.group_key() == self.slow_room {
+                tokio::time::sleep(self.delay).await;
+            }
+            self.inner.save(game).await
+        }
+        async fn remove(&self, group_key: &GroupKey) -> Result<()> { self.inner.remove(group_key).await }
+        async fn load(&self, group_key: &GroupKey) -> Result<Option<Game>> { self.inner.load(group_key).await }
+        async fn load_all(&self) -> Result<Vec<Game>> { self.inner.load_all().await }
+        async fn try_lock(&self, group_key: &GroupKey, token: &str, ttl: Duration) -> Result<bool> { self.inner.try_lock(group_key, token, ttl).await }
+        async fn unlock(&self, group_key: &GroupKey, token: &str) -> Result<()> { self.inner.unlock(group_key, token).await }
+        async fn claim_round(&self, group_key: &GroupKey, round_id: u64, user_id: &str) -> Result<RoundClaim> { self.inner.claim_round(group_key, round_id, user_id).await }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn many_rooms_do_not_wait_for_a_slow_room() {
+        const ROOMS: usize = 200;
+        const SKIPS: usize = 5;
+        let slow_room = "slow".to_string();
+        let store = Arc::new(SlowRoomStore {
+            inner: InMemoryGameStore::default(),
+            slow_room: slow_room.clone(),
+            delay: Duration::from_millis(300),
+        });
+        let gm = test_manager(store, false);
+        gm.start_game(slow_room.clone(), math_options()).await.unwrap();
+        for i in 0..ROOMS {
+            gm.start_game(format!("room{}", i), math_options()).await.unwrap();
+        }
+
+        let slow = {
+            let (gm, slow_room) = (gm.clone(), slow_room.clone());
+            tokio::spawn(async move {
+                for _ in 0..SKIPS {
+                    gm.skip_question(&slow_room).await.unwrap();
+                }
+            })
+        };
+        let started = std::time::Instant::now();
+        let rooms: Vec<_> = (0..ROOMS)
+            .map(|i| {
+                let gm = gm.clone();
+                tokio::spawn(async move {
+                    let group_key = format!("room{}", i);
+                    for _ in 0..SKIPS {
+                        gm.skip_question(&group_key).await.unwrap();
+                        gm.try_answer_inmemory("user", &group_key, "오답").await.unwrap();
+                    }
+                })
+            })
+            .collect();
+        for room in rooms {
+            room.await.unwrap();
+        }
+
+        // 느린 방 하나가 끝나기(5 * 300ms)를 기다리지 않았다
+        assert!(started.elapsed() < Duration::from_millis(1000), "{:?}", started.elapsed());
+        slow.await.unwrap();
+        assert_eq!(gm.status().await.active_games, ROOMS + 1);
+    }
+
+    #[tokio::test]
+    async fn concurrent_updates_are_saved_in_order() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::default());
+        let group_key = "room".to_string();
+        let gm = test_manager(store.clone(), false);
+        gm.start_game(group_key.clone(), math_options()).await.unwrap();
+
+        let skips: Vec<_> = (0..20)
+            .map(|_| {
+                let (gm, group_key) = (gm.clone(), group_key.clone());
+                tokio::spawn(async move { gm.skip_question(&group_key).await.map(|result| result.next_quiz.is_some()) })
+            })
+            .collect();
+        for skip in skips {
+            assert!(skip.await.unwrap().unwrap());
+        }
+
+        // 늦게 도착한 예전 상태가 덮어쓰지 않았다
+        let saved = store.load(&group_key).await.unwrap().unwrap();
+        assert_eq!(saved.version, 21);
+    }
+
+    // 저장소가 아니라 게임 task에서 차지한다. 이 저장소는 차지를 받지 않는다
+    struct NoClaimStore(InMemoryGameStore);
+
+    #[async_trait::async_trait]
+    impl GameStore for NoClaimStore {
+        async fn save(&self, game: &Game) -> Result<()> { self.0.save(game).await }
+        async fn remove(&self, group_key: &GroupKey) -> Result<()> { self.0.remove(group_key).await }
+        async fn load(&self, group_key: &GroupKey) -> Result<Option<Game>> { self.0.load(group_key).await }
+        async fn load_all(&self) -> Result<Vec<Game>> { self.0.load_all().await }
+        async fn try_lock(&self, group_key: &GroupKey, token: &str, ttl: Duration) -> Result<bool> { self.0.try_lock(group_key, token, ttl).await }
+        async fn unlock(&self, group_key: &GroupKey, token: &str) -> Result<()> { self.0.unlock(group_key, token).await }
+        async fn claim_round(&self, group_key: &GroupKey, _round_id: u64, _user_id: &str) -> Result<RoundClaim> {
+            Err(Error::RedisCommandFail(group_key.clone()))
+        }
+    }
+
+    // 모두 첫 문제의 답을 한꺼번에 보낸다. 첫 문제의 id와 결과들
+    async fn answer_in_parallel(gm: &GameManager, group_key: &GroupKey, players: usize) -> (String, Vec<AnswerResult>) {
+        let game = gm.start_game(group_key.clone(), math_options()).await.unwrap();
+        let QuizType::Math(quiz) = &game.current_quiz else {
+            unreachable!();
+        };
+        let answer = quiz.answer.to_string();
+
+        let answers: Vec<_> = (0..players)
+            .map(|i| {
+                let (gm, group_key, answer) = (gm.clone(), group_key.clone(), answer.clone());
+                tokio::spawn(async move { gm.try_answer_inmemory(&format!("user{}", i), &group_key, &answer).await })
+            })
+            .collect();
+        let mut results = Vec::new();
+        for answer in answers {
+            results.push(answer.await.unwrap().unwrap());
+        }
+        (game.current_quiz.as_question().question_id(), results)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn parallel_correct_answers_have_one_winner() {
+        let gm = test_manager(Arc::new(NoClaimStore(InMemoryGameStore::default())), false);
+        let (first_quiz, results) = answer_in_parallel(&gm, &"room".to_string(), 16).await;
+        assert_one_winner(&first_quiz, &results);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn parallel_correct_answers_have_one_winner_across_instances() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::default());
+        let gm = test_manager(store, true);
+        let (first_quiz, results) = answer_in_parallel(&gm, &"room".to_string(), 16).await;
+        assert_one_winner(&first_quiz, &results);
+    }
+
+    fn math_answer(game: &Game) -> String {
+        let QuizType::Math(quiz) = &game.current_quiz else {
+            unreachable!();
+        };
+        quiz.answer.to_string()
+    }
+
+    #[tokio::test]
+    async fn correct_answers_add_cumulative_scores() {
+        let gm = test_manager(Arc::new(InMemoryGameStore::default()), false);
+        let group_key = "room".to_string();
+        let game = gm.start_game(group_key.clone(), math_options()).await.unwrap();
+        assert_eq!(gm.get_ranking("alice", &group_key).await.unwrap(), (None, None));
+
+        let mut answer = math_answer(&game);
+        for expected in [1, 2] {
+            let result = gm.try_answer_inmemory("alice", &group_key, &answer).await.unwrap();
+            let AnswerResult::Correct { score, next_quiz, .. } = result else {
+                panic!("not correct");
+            };
+            assert_eq!(score, Some(expected));
+            let QuizType::Math(quiz) = *next_quiz.unwrap() else {
+                unreachable!();
+            };
+            answer = quiz.answer.to_string();
+        }
+        gm.try_answer_inmemory("bob", &group_key, &answer).await.unwrap();
+
+        let top_scores = gm.profiles().top_scores(10).await.unwrap();
+        assert_eq!(top_scores, vec![("alice".to_string(), 2), ("bob".to_string(), 1)]);
+        assert_eq!(gm.get_ranking("bob", &group_key).await.unwrap(), (Some(2), Some(1)));
+    }
+
+    #[tokio::test]
+    async fn only_finished_games_are_counted() {
+        let scores = Arc::new(InMemoryScoreStore::default());
+        let gm = test_manager_with(Arc::new(InMemoryGameStore::default()), scores.clone(), false, Arc::new(LogEventClient));
+        let group_key = "room".to_string();
+
+        // 중지한 게임은 세지 않는다
+        let game = gm.start_game(group_key.clone(), math_options()).await.unwrap();
+        gm.try_answer_inmemory("alice", &group_key, &math_answer(&game)).await.unwrap();
+        gm.stop_game(group_key.clone()).await.unwrap();
+
+        // 방치돼서 끝난 게임도
+        let game = gm.start_game(group_key.clone(), math_options()).await.unwrap();
+        gm.try_answer_inmemory("alice", &group_key, &math_answer(&game)).await.unwrap();
+        gm.reap_idle_games(Duration::ZERO).await;
+
+        // 끝까지 푼 게임만
+        let game = gm.start_game(group_key.clone(), math_options()).await.unwrap();
+        let mut next_quiz = Some(game.current_quiz);
+        while let Some(QuizType::Math(quiz)) = next_quiz {
+            let AnswerResult::Correct { next_quiz: next, .. } = gm.try_answer_inmemory("alice", &group_key, &quiz.answer.to_string()).await.unwrap() else {
+                panic!("not correct");
+            };
+            next_quiz = next.map(|quiz| *quiz);
+        }
+
+        // 게임 수는 백그라운드에서 기록한다
+        wait_until(|| async { scores.user_records("alice").await.unwrap().games_played > 0 }).await;
+        let records = scores.user_records("alice").await.unwrap();
+        assert_eq!(records.games_played, 1);
+        assert_eq!(records.first_places, 1);
+        assert_eq!(records.score, 2 + MAX_ROUNDS as u32);
+    }
+
+    fn assert_one_winner(first_quiz: &str, results: &[AnswerResult]) {
+        let winners: Vec<&String> = results.iter()
+            .filter_map(|result| match result {
+                AnswerResult::Correct { user_id, current_quiz, .. } if current_quiz.as_question().question_id() == *first_quiz => Some(user_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(winners.len(), 1);
+
+        // 나머지는 늦었다 (다음 문제의 답이 우연히 같으면 그 문제를 맞힌다)
+        for result in results {
+            match result {
+                AnswerResult::Late { winner } => assert_eq!(winner.as_ref(), Some(winners[0])),
+                AnswerResult::Correct { .. } => {}
+                _ => panic!("unexpected result"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn two_instances_share_one_store() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::default());
+        let group_key = "room".to_string();
+        let (gm_a, gm_b) = (test_manager(store.clone(), true), test_manager(store.clone(), true));
+        gm_a.start_game(group_key.clone(), math_options()).await.unwrap();
+
+        // 다른 인스턴스에서 시작한 게임을 이어서
+        let skipped = gm_b.skip_question(&group_key).await.unwrap();
+        gm_a.pause_game(&group_key).await.unwrap();
+        assert!(matches!(gm_b.skip_question(&group_key).await, Err(Error::GamePaused(_))));
+        let resumed = gm_b.resume_game(&group_key).await.unwrap();
+        assert_eq!(
+            resumed.current_quiz.as_question().question_id(),
+            skipped.next_quiz.unwrap().as_question().question_id(),
+        );
+
+        // 두 인스턴스가 한꺼번에 받아도 하나씩 처리된다
+        let skips: Vec<_> = (0..10)
+            .map(|i| {
+                let gm = if i % 2 == 0 { gm_a.clone() } else { gm_b.clone() };
+                let group_key = group_key.clone();
+                tokio::spawn(async move { gm.skip_question(&group_key).await.is_ok() })
+            })
+            .collect();
+        for skip in skips {
+            assert!(skip.await.unwrap());
+        }
+        assert_eq!(store.load(&group_key).await.unwrap().unwrap().version, 5 + 10);
+
+        gm_b.stop_game(group_key.clone()).await.unwrap();
+        assert!(matches!(gm_a.skip_question(&group_key).await, Err(Error::GameNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn lobbies_and_challenges_are_refused_across_instances() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::default());
+        let group_key = "room".to_string();
+        let (gm_a, gm_b) = (test_manager(store.clone(), true), test_manager(store.clone(), true));
+
+        // A에서 연 대기실에 B로 "참가"가 와도 찾지 못하니 처음부터 열지 않는다
+        assert!(matches!(gm_a.open_lobby(group_key.clone(), "host", math_options()).await, Err(Error::GameNotDistributable(_))));
+        assert!(matches!(gm_b.join_lobby(&group_key, "guest").await, Err(Error::GameNotDistributable(_))));
+        assert!(matches!(gm_b.start_lobby(&group_key, "host").await, Err(Error::GameNotDistributable(_))));
+
+        // 도전도 마찬가지
+        assert!(matches!(gm_a.create_challenge(group_key.clone(), math_options()).await, Err(Error::GameNotDistributable(_))));
+        assert!(matches!(gm_b.accept_challenge("other".to_string(), "ABCDEF").await, Err(Error::GameNotDistributable(_))));
+
+        // 어느 쪽에서도 게임이 시작되지 않았다
+        assert!(store.load(&group_key).await.unwrap().is_none());
+        assert!(store.load(&"other".to_string()).await.unwrap().is_none());
+        assert_eq!(gm_a.status().await.open_lobbies, 0);
+    }
+
+    #[tokio::test]
+    async fn stale_instance_cannot_overwrite() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::default());
+        let group_key = "room".to_string();
+        let (gm_a, gm_b) = (test_manager(store.clone(), true), test_manager(store.clone(), true));
+        gm_a.start_game(group_key.clone(), math_options()).await.unwrap();
+
+        // A가 읽은 뒤 잠금이 풀린 사이에 B가 바꿨다
+        let stale = {
+            let _lock = gm_a.lock_group(&group_key).await.unwrap();
+            gm_a.handle(&group_key).await.unwrap()
+        };
+        gm_b.skip_question(&group_key).await.unwrap();
+
+        assert!(matches!(stale.update(Game::touch).await, Err(Error::GameBusy(_))));
+        assert_eq!(store.load(&group_key).await.unwrap().unwrap().version, 2);
+    }
 }
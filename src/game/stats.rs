@@ -0,0 +1,349 @@
+//! 문제별 통계 (출제/정답/패스 횟수, 푸는 데 걸린 시간)
+//!
+//! Redis key structure
+//! ---
+//! - key: `question_stats:{question_id}` (HASH) / fields: `asked`, `solved`, `skipped`, `solve_ms_total`
+//! - key: `question_stats_ids` (SET): 통계가 있는 question_id 목록
+//! - key: `question_review` (SET): 검토가 필요한 question_id 목록
+//!
+//! question_id는 `{유형}:{국가 코드 또는 문제 텍스트의 해시}`라서 admin url 경로에 그대로 쓸 수 있다.
+//!
+//! 통계가 충분히 쌓인 문제는 손으로 매긴 난이도 대신 실제 정답률/시간으로 난이도를 정한다.
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use redis::AsyncCommands;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::{Error, Result};
+use crate::conn::RedisConnectionPool;
+use crate::game::model::Difficulty;
+
+const REDIS_QUESTION_STATS_KEY_PREFIX: &str = "question_stats:";
+const REDIS_QUESTION_STATS_IDS_KEY: &str = "question_stats_ids";
+const REDIS_QUESTION_REVIEW_KEY: &str = "question_review";
+
+// 이보다 적게 출제된 문제는 판단하지 않는다
+const MIN_ASKED_FOR_DIFFICULTY: u64 = 5;
+const MIN_ASKED_FOR_REVIEW: u64 = 10;
+// 평균 이보다 빨리 풀리면 너무 쉬운(또는 답이 새는) 문제
+const INSTANT_SOLVE: Duration = Duration::from_secs(3);
+
+const EMPIRICAL_DIFFICULTY_REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QuestionStats {
+    pub question_id: String,
+    pub asked: u64,
+    pub solved: u64,
+    pub skipped: u64,
+    pub solve_ms_total: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewReason {
+    NeverSolved,
+    SolvedInstantly,
+}
+
+impl QuestionStats {
+    fn from_hash(question_id: String, hash: HashMap<String, u64>) -> Self {
+        let field = |name: &str| hash.get(name).copied().unwrap_or(0);
+        Self {
+            question_id,
+            asked: field("asked"),
+            solved: field("solved"),
+            skipped: field("skipped"),
+            solve_ms_total: field("solve_ms_total"),
+        }
+    }
+
+    pub fn solve_rate(&self) -> Option<f64> {
+        (self.asked > 0).then(|| self.solved as f64 / self.asked as f64)
+    }
+
+    pub fn average_solve_time(&self) -> Option<Duration> {
+        (self.solved > 0).then(|| Duration::from_millis(self.solve_ms_total / self.solved))
+    }
+
+    /// 표본이 적으면 None
+    pub fn empirical_difficulty(&self) -> Option<Difficulty> {
+        if self.asked < MIN_ASKED_FOR_DIFFICULTY {
+            return None;
+        }
+
+        let solve_rate = self.solve_rate()?;
+        let average_solve_time = self.average_solve_time().unwrap_or(Duration::MAX);
+        let difficulty = if solve_rate >= 0.8 && average_solve_time < Duration::from_secs(15) {
+            Difficulty::Easy
+        } else if solve_rate < 0.4 || average_solve_time > Duration::from_secs(45) {
+            Difficulty::Hard
+        } else {
+            Difficulty::Normal
+        };
+        Some(difficulty)
+    }
+
+    pub fn review_reason(&self) -> Option<ReviewReason> {
+        if self.asked < MIN_ASKED_FOR_REVIEW {
+            return None;
+        }
+
+        if self.solved == 0 {
+            return Some(ReviewReason::NeverSolved);
+        }
+        if self.average_solve_time().is_some_and(|time| time < INSTANT_SOLVE) {
+            return Some(ReviewReason::SolvedInstantly);
+        }
+        None
+    }
+}
+
+/// admin 리포트 한 줄
+#[derive(Debug, Serialize)]
+pub struct QuestionStatsReport {
+    #[serde(flatten)]
+    pub stats: QuestionStats,
+    pub solve_rate: Option<f64>,
+    pub average_solve_secs: Option<f64>,
+    pub empirical_difficulty: Option<&'static str>,
+    pub review_reason: Option<ReviewReason>,
+}
+
+impl From<QuestionStats> for QuestionStatsReport {
+    fn from(stats: QuestionStats) -> Self {
+        Self {
+            solve_rate: stats.solve_rate(),
+            average_solve_secs: stats.average_solve_time().map(|time| time.as_secs_f64()),
+            empirical_difficulty: stats.empirical_difficulty().map(|difficulty| difficulty.name()),
+            review_reason: stats.review_reason(),
+            stats,
+        }
+    }
+}
+
+pub enum StatsEvent {
+    Asked(String),
+    Solved(String, Duration),
+    Skipped(String),
+}
+
+#[derive(Clone)]
+pub struct QuestionStatsStore {
+    pool: RedisConnectionPool,
+}
+
+impl QuestionStatsStore {
+    pub fn new(pool: RedisConnectionPool) -> Self {
+        Self { pool }
+    }
+
+    /// 응답을 늦추지 않도록 백그라운드에서 순서대로 기록한다. 실패해도 게임에는 영향 없음
+    pub fn record(&self, events: Vec<StatsEvent>) {
+        let store = self.clone();
+        tokio::spawn(async move {
+            for event in events {
+                let result = match &event {
+                    StatsEvent::Asked(question_id) => store.record_asked(question_id).await,
+                    StatsEvent::Solved(question_id, elapsed) => store.record_solved(question_id, *elapsed).await,
+                    StatsEvent::Skipped(question_id) => store.record_skipped(question_id).await,
+                };
+                if let Err(e) = result {
+                    warn!("{:<12} - record failed: {:?}", "STATS", e);
+                }
+            }
+        });
+    }
+
+    pub async fn record_asked(&self, question_id: &str) -> Result<()> {
+        self.incr(question_id, &[("asked", 1)]).await
+    }
+
+    pub async fn record_solved(&self, question_id: &str, elapsed: Duration) -> Result<()> {
+        self.incr(question_id, &[("solved", 1), ("solve_ms_total", elapsed.as_millis() as i64)]).await
+    }
+
+    pub async fn record_skipped(&self, question_id: &str) -> Result<()> {
+        self.incr(question_id, &[("skipped", 1)]).await
+    }
+
+    async fn incr(&self, question_id: &str, fields: &[(&str, i64)]) -> Result<()> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let redis_key = format!("{}{}", REDIS_QUESTION_STATS_KEY_PREFIX, question_id);
+        let mut pipe = redis::pipe();
+        for (field, delta) in fields {
+            pipe.hincr(&redis_key, *field, *delta).ignore();
+        }
+        pipe.sadd(REDIS_QUESTION_STATS_IDS_KEY, question_id).ignore();
+
+        pipe.query_async::<_, ()>(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(redis_key))
+    }
+
+    pub async fn all_stats(&self) -> Result<Vec<QuestionStats>> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let question_ids: Vec<String> = conn.smembers(REDIS_QUESTION_STATS_IDS_KEY)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_QUESTION_STATS_IDS_KEY.to_string()))?;
+        if question_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut pipe = redis::pipe();
+        for question_id in &question_ids {
+            pipe.hgetall(format!("{}{}", REDIS_QUESTION_STATS_KEY_PREFIX, question_id));
+        }
+        let hashes: Vec<HashMap<String, u64>> = pipe.query_async(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_QUESTION_STATS_KEY_PREFIX.to_string()))?;
+
+        Ok(question_ids.into_iter()
+            .zip(hashes)
+            .map(|(question_id, hash)| QuestionStats::from_hash(question_id, hash))
+            .collect())
+    }
+
+    pub async fn review_list(&self) -> Result<Vec<String>> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        conn.smembers(REDIS_QUESTION_REVIEW_KEY)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_QUESTION_REVIEW_KEY.to_string()))
+    }
+
+    /// 검토 끝난 문제를 목록에서 뺀다. 통계가 그대로면 다음 갱신 때 다시 들어온다
+    pub async fn dismiss_review(&self, question_id: &str) -> Result<()> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        conn.srem(REDIS_QUESTION_REVIEW_KEY, question_id)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_QUESTION_REVIEW_KEY.to_string()))
+    }
+
+    /// 통계로 난이도 캐시를 갱신하고, 이상한 문제는 검토 목록에 넣는다
+    pub async fn refresh(&self) -> Result<()> {
+        let all_stats = self.all_stats().await?;
+
+        let difficulties: HashMap<String, Difficulty> = all_stats.iter()
+            .filter_map(|stats| Some((stats.question_id.clone(), stats.empirical_difficulty()?)))
+            .collect();
+        let review: Vec<&str> = all_stats.iter()
+            .filter(|stats| stats.review_reason().is_some())
+            .map(|stats| stats.question_id.as_str())
+            .collect();
+        debug!("{:<12} - refreshed, {} difficulties, {} to review", "STATS", difficulties.len(), review.len());
+
+        *empirical_difficulties().write().unwrap() = difficulties;
+
+        if !review.is_empty() {
+            let mut conn = self.pool.get()
+                .await
+                .map_err(|_| Error::RedisConnectionGetFail)?;
+            conn.sadd::<_, _, ()>(REDIS_QUESTION_REVIEW_KEY, review)
+                .await
+                .map_err(|_| Error::RedisCommandFail(REDIS_QUESTION_REVIEW_KEY.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn spawn_refresh_task(&self) {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(EMPIRICAL_DIFFICULTY_REFRESH_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = store.refresh().await {
+                    warn!("{:<12} - refresh failed: {:?}", "STATS", e);
+                }
+            }
+        });
+    }
+}
+
+/// 문제 텍스트로 만드는 question_id용 해시 (FNV-1a 64bit).
+/// 텍스트에 '/' 등이 있어도 url 경로에 넣을 수 있고, 재시작하거나 버전이 바뀌어도 같은 값이다
+pub fn stable_hash(text: &str) -> String {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+    let hash = text.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME));
+    format!("{:016x}", hash)
+}
+
+fn empirical_difficulties() -> &'static RwLock<HashMap<String, Difficulty>> {
+    static INSTANCE: OnceLock<RwLock<HashMap<String, Difficulty>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// 마지막 갱신 기준. 표본이 부족했던 문제는 None
+pub fn empirical_difficulty(question_id: &str) -> Option<Difficulty> {
+    empirical_difficulties().read().unwrap().get(question_id).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(asked: u64, solved: u64, average_solve_ms: u64) -> QuestionStats {
+        QuestionStats {
+            question_id: "q".to_string(),
+            asked,
+            solved,
+            skipped: asked - solved,
+            solve_ms_total: solved * average_solve_ms,
+        }
+    }
+
+    #[test]
+    fn empirical_difficulty_thresholds() {
+        let cases = [
+            // 표본이 부족하면 판단하지 않는다
+            (stats(4, 4, 1_000), None),
+            (stats(5, 5, 1_000), Some(Difficulty::Easy)),
+            // 정답률 80% 이상 + 평균 15초 미만이면 쉬움
+            (stats(5, 4, 14_999), Some(Difficulty::Easy)),
+            (stats(5, 4, 15_000), Some(Difficulty::Normal)),
+            (stats(10, 7, 5_000), Some(Difficulty::Normal)),
+            // 정답률 40% 미만 또는 평균 45초 초과면 어려움
+            (stats(5, 2, 30_000), Some(Difficulty::Normal)),
+            (stats(5, 1, 30_000), Some(Difficulty::Hard)),
+            (stats(5, 0, 0), Some(Difficulty::Hard)),
+            (stats(5, 3, 45_000), Some(Difficulty::Normal)),
+            (stats(5, 3, 45_001), Some(Difficulty::Hard)),
+        ];
+        for (stats, expected) in cases {
+            assert_eq!(stats.empirical_difficulty(), expected, "{:?}", stats);
+        }
+    }
+
+    #[test]
+    fn review_reason_thresholds() {
+        let cases = [
+            (stats(9, 0, 0), None),
+            (stats(9, 9, 100), None),
+            (stats(10, 0, 0), Some(ReviewReason::NeverSolved)),
+            (stats(10, 1, 2_999), Some(ReviewReason::SolvedInstantly)),
+            (stats(10, 10, 3_000), None),
+            (stats(10, 5, 20_000), None),
+        ];
+        for (stats, expected) in cases {
+            assert_eq!(stats.review_reason(), expected, "{:?}", stats);
+        }
+    }
+}
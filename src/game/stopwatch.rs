@@ -0,0 +1,66 @@
+//! 멈출 수 있는 타이머
+//!
+//! `Instant`는 프로세스 밖으로 가져갈 수 없어서, 지금까지 흐른 시간(`accumulated`)과
+//! 다시 흐르기 시작한 시점(`running_since`)으로 나눠 둔다. 일시정지 중에는 시간이 흐르지 않는다.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StopwatchSnapshot", into = "StopwatchSnapshot")]
+pub struct Stopwatch {
+    accumulated: Duration,
+    // 멈춰 있으면 None
+    running_since: Option<Instant>,
+}
+
+impl Stopwatch {
+    pub fn start() -> Self {
+        Self {
+            accumulated: Duration::ZERO,
+            running_since: Some(Instant::now()),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.accumulated + self.running_since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    pub fn pause(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.accumulated += since.elapsed();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.running_since.is_none() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+}
+
+// 저장할 때는 그때까지 흐른 시간만 남긴다. 읽어 들인 시점부터 다시 흐른다
+#[derive(Serialize, Deserialize)]
+struct StopwatchSnapshot {
+    elapsed_ms: u64,
+    running: bool,
+}
+
+impl From<Stopwatch> for StopwatchSnapshot {
+    fn from(stopwatch: Stopwatch) -> Self {
+        Self {
+            elapsed_ms: stopwatch.elapsed().as_millis() as u64,
+            running: stopwatch.running_since.is_some(),
+        }
+    }
+}
+
+impl From<StopwatchSnapshot> for Stopwatch {
+    fn from(snapshot: StopwatchSnapshot) -> Self {
+        Self {
+            accumulated: Duration::from_millis(snapshot.elapsed_ms),
+            running_since: snapshot.running.then(Instant::now),
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! 진행 중인 게임 저장소
+//!
+//! 게임 상태가 바뀔 때마다 통째로(JSON) 써 두고, 재시작하면 다시 읽어서 이어간다.
+//! 어디에 쓸지는 `GAME_STORE` 설정으로 고른다.
+//!
+//! 여러 인스턴스를 띄울 때(`DISTRIBUTED`)는 저장소가 원본이다. 방마다 잠금을 잡고, 읽고, 바꾸고, 쓴다.
+//!
+//! 게임은 쓸 때마다 `version`이 하나씩 오른다. 저장소는 이미 가진 것보다 `version`이 높은 게임만 받는다.
+//! 순서가 뒤바뀐 저장이나, 잠금이 풀린 뒤에 늦게 도착한 다른 인스턴스의 저장은 버려진다.
+//!
+//! 대기실과 도전(`ChallengeBoard`)은 저장하지 않는다. 재시작하면 열려 있던 대기실은 사라지고,
+//! 도전 게임은 이어서 풀 수 있지만 두 방의 결과는 비교하지 못한다.
+//! 같은 이유로 여러 인스턴스일 때는 대기실과 도전을 열지 않는다 (`GameNotDistributable`).
+//!
+//! Redis key structure
+//! ---
+//! - key: `games` (HASH) / field: `{group_key}` / value: `Game` JSON
+//! - key: `games:version` (HASH) / field: `{group_key}` / value: 마지막으로 쓴 `version`
+//! - key: `games:lock:{group_key}` / value: `{token}` (PX `GAME_LOCK_TTL`)
+//! - key: `games:round:{group_key}:{round_id}` / value: `{user_id}` (그 문제를 먼저 맞힌 사람)
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use tracing::warn;
+
+use crate::{Error, Result};
+use crate::config::GameStoreConfig;
+use crate::conn::RedisConnectionPool;
+use crate::game::state::{Game, GroupKey};
+
+const REDIS_GAMES_KEY: &str = "games";
+const REDIS_VERSIONS_KEY: &str = "games:version";
+const REDIS_LOCK_KEY_PREFIX: &str = "games:lock:";
+const REDIS_ROUND_KEY_PREFIX: &str = "games:round:";
+const SNAPSHOT_FILE_EXT: &str = "json";
+const LOCK_FILE_EXT: &str = "lock";
+const CLAIM_FILE_EXT: &str = "claim";
+
+// 늦게 온 답에 누가 맞혔는지 알려줄 만큼만 남긴다
+const ROUND_CLAIM_TTL: Duration = Duration::from_secs(60);
+
+// 잡은 채로 죽어도 이 시간이 지나면 풀린다. 요청 하나 처리하기에 충분히 길게
+const GAME_LOCK_TTL: Duration = Duration::from_secs(5);
+// 이만큼 기다려도 못 잡으면 포기 (카카오 스킬 응답 제한 5초 안에)
+const GAME_LOCK_WAIT: Duration = Duration::from_secs(3);
+const GAME_LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(20);
+
+// 내가 잡은 잠금일 때만 지운다
+const REDIS_UNLOCK_SCRIPT: &str = r#"
+if redis.call("get", KEYS[1]) == ARGV[1] then
+    return redis.call("del", KEYS[1])
+else
+    return 0
+end
+"#;
+
+// 저장된 version보다 높을 때만 쓴다
+const REDIS_SAVE_SCRIPT: &str = r#"
+local stored = tonumber(redis.call("hget", KEYS[2], ARGV[1]) or "0")
+if stored >= tonumber(ARGV[2]) then
+    return 0
+end
+redis.call("hset", KEYS[1], ARGV[1], ARGV[3])
+redis.call("hset", KEYS[2], ARGV[1], ARGV[2])
+return 1
+"#;
+
+#[async_trait]
+pub trait GameStore: Send + Sync {
+    /// 저장된 게임보다 `version`이 높을 때만 쓴다. 아니면 `GameBusy`
+    async fn save(&self, game: &Game) -> Result<()>;
+    async fn remove(&self, group_key: &GroupKey) -> Result<()>;
+    async fn load(&self, group_key: &GroupKey) -> Result<Option<Game>>;
+    /// 읽을 수 없는 게임(형식이 바뀐 예전 데이터 등)은 건너뛴다
+    async fn load_all(&self) -> Result<Vec<Game>>;
+
+    /// 다른 누가 잡고 있으면 false. `ttl`이 지나면 저절로 풀린다
+    async fn try_lock(&self, group_key: &GroupKey, token: &str, ttl: Duration) -> Result<bool>;
+    /// `token`이 다르면(이미 만료돼서 다른 누가 잡았으면) 아무것도 안 한다
+    async fn unlock(&self, group_key: &GroupKey, token: &str) -> Result<()>;
+
+    /// 한 문제(`round_id`)에 한 명만 이긴다. 먼저 맞힌 사람이 있으면 그 사람
+    async fn claim_round(&self, group_key: &GroupKey, round_id: u64, user_id: &str) -> Result<RoundClaim>;
+}
+
+pub enum RoundClaim {
+    Won,
+    Lost { winner: String },
+}
+
+/// 방 잠금을 잡을 때까지 기다린다. 풀 때 쓸 token을 돌려준다
+pub async fn lock(store: &dyn GameStore, group_key: &GroupKey) -> Result<String> {
+    let token = format!("{:016x}", rand::random::<u64>());
+    let deadline = Instant::now() + GAME_LOCK_WAIT;
+    loop {
+        if store.try_lock(group_key, &token, GAME_LOCK_TTL).await? {
+            return Ok(token);
+        }
+        if Instant::now() >= deadline {
+            return Err(Error::GameBusy(group_key.clone()));
+        }
+        tokio::time::sleep(GAME_LOCK_RETRY_INTERVAL).await;
+    }
+}
+
+pub fn game_store(store_config: &GameStoreConfig, pool: RedisConnectionPool) -> Arc<dyn GameStore> {
+    match store_config {
+        GameStoreConfig::Redis => Arc::new(RedisGameStore { pool }),
+        GameStoreConfig::File(dir) => Arc::new(FileGameStore::new(dir.clone())),
+        GameStoreConfig::Memory => Arc::new(InMemoryGameStore::default()),
+    }
+}
+
+fn to_json(game: &Game) -> Result<String> {
+    serde_json::to_string(game).map_err(|e| Error::GameStoreFail(e.to_string()))
+}
+
+fn check_version(game: &Game, stored_version: Option<u64>) -> Result<()> {
+    match stored_version {
+        Some(stored_version) if stored_version >= game.version => Err(Error::GameBusy(game.group_key().clone())),
+        _ => Ok(()),
+    }
+}
+
+fn from_json(key: &str, json: &str) -> Option<Game> {
+    serde_json::from_str(json)
+        .map_err(|e| warn!("{:<12} - snapshot of {} not loaded: {}", "GAME_STORE", key, e))
+        .ok()
+}
+
+pub struct RedisGameStore {
+    pool: RedisConnectionPool,
+}
+
+#[async_trait]
+impl GameStore for RedisGameStore {
+    async fn save(&self, game: &Game) -> Result<()> {
+        let json = to_json(game)?;
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let saved: i64 = redis::Script::new(REDIS_SAVE_SCRIPT)
+            .key(REDIS_GAMES_KEY)
+            .key(REDIS_VERSIONS_KEY)
+            .arg(game.group_key())
+            .arg(game.version)
+            .arg(json)
+            .invoke_async(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_GAMES_KEY.to_string()))?;
+
+        match saved {
+            0 => Err(Error::GameBusy(game.group_key().clone())),
+            _ => Ok(()),
+        }
+    }
+
+    async fn remove(&self, group_key: &GroupKey) -> Result<()> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        redis::pipe()
+            .atomic()
+            .hdel(REDIS_GAMES_KEY, group_key).ignore()
+            .hdel(REDIS_VERSIONS_KEY, group_key).ignore()
+            .query_async(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_GAMES_KEY.to_string()))
+    }
+
+    async fn load(&self, group_key: &GroupKey) -> Result<Option<Game>> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let json: Option<String> = conn.hget(REDIS_GAMES_KEY, group_key)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_GAMES_KEY.to_string()))?;
+
+        Ok(json.and_then(|json| from_json(group_key, &json)))
+    }
+
+    async fn load_all(&self) -> Result<Vec<Game>> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let snapshots: HashMap<String, String> = conn.hgetall(REDIS_GAMES_KEY)
+            .await
+            .map_err(|_| Error::RedisCommandFail(REDIS_GAMES_KEY.to_string()))?;
+
+        Ok(snapshots.iter()
+            .filter_map(|(group_key, json)| from_json(group_key, json))
+            .collect())
+    }
+
+    async fn try_lock(&self, group_key: &GroupKey, token: &str, ttl: Duration) -> Result<bool> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        // SET key token NX PX ttl
+        let redis_key = format!("{}{}", REDIS_LOCK_KEY_PREFIX, group_key);
+        let locked: Option<String> = redis::cmd("SET")
+            .arg(&redis_key)
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl.as_millis() as u64)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(redis_key))?;
+
+        Ok(locked.is_some())
+    }
+
+    async fn unlock(&self, group_key: &GroupKey, token: &str) -> Result<()> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        let redis_key = format!("{}{}", REDIS_LOCK_KEY_PREFIX, group_key);
+        redis::Script::new(REDIS_UNLOCK_SCRIPT)
+            .key(&redis_key)
+            .arg(token)
+            .invoke_async::<_, i64>(&mut *conn)
+            .await
+            .map(|_| ())
+            .map_err(|_| Error::RedisCommandFail(redis_key))
+    }
+
+    async fn claim_round(&self, group_key: &GroupKey, round_id: u64, user_id: &str) -> Result<RoundClaim> {
+        let mut conn = self.pool.get()
+            .await
+            .map_err(|_| Error::RedisConnectionGetFail)?;
+
+        // SET NX 후 GET. 이겼으면 내 user_id가 나온다
+        let redis_key = format!("{}{}:{}", REDIS_ROUND_KEY_PREFIX, group_key, round_id);
+        let (winner,): (String,) = redis::pipe()
+            .atomic()
+            .cmd("SET").arg(&redis_key).arg(user_id).arg("NX").arg("PX").arg(ROUND_CLAIM_TTL.as_millis() as u64).ignore()
+            .get(&redis_key)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|_| Error::RedisCommandFail(redis_key))?;
+
+        Ok(round_claim(user_id, winner))
+    }
+}
+
+fn round_claim(user_id: &str, winner: String) -> RoundClaim {
+    if winner == user_id {
+        RoundClaim::Won
+    } else {
+        RoundClaim::Lost { winner }
+    }
+}
+
+/// 방 하나에 파일 하나. 파일 이름은 group_key를 hex로
+pub struct FileGameStore {
+    dir: PathBuf,
+    // 저장된 version을 읽고 쓰는 사이에 끼어들지 않도록
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl FileGameStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir, write_lock: tokio::sync::Mutex::new(()) }
+    }
+
+    fn path(&self, group_key: &GroupKey) -> PathBuf {
+        self.path_with_ext(group_key, SNAPSHOT_FILE_EXT)
+    }
+
+    // 여러 인스턴스가 같은 디렉토리(NFS 등)를 볼 때를 위한 잠금 파일
+    fn lock_path(&self, group_key: &GroupKey) -> PathBuf {
+        self.path_with_ext(group_key, LOCK_FILE_EXT)
+    }
+
+    fn claim_path(&self, group_key: &GroupKey, round_id: u64) -> PathBuf {
+        self.path_with_ext(group_key, &format!("{}.{}", round_id, CLAIM_FILE_EXT))
+    }
+
+    fn path_with_ext(&self, group_key: &GroupKey, ext: &str) -> PathBuf {
+        let name: String = group_key.bytes().map(|b| format!("{:02x}", b)).collect();
+        self.dir.join(format!("{}.{}", name, ext))
+    }
+}
+
+#[async_trait]
+impl GameStore for FileGameStore {
+    async fn save(&self, game: &Game) -> Result<()> {
+        let json = to_json(game)?;
+        let _write_lock = self.write_lock.lock().await;
+        let stored = self.load(game.group_key()).await?;
+        check_version(game, stored.map(|stored| stored.version))?;
+
+        let path = self.path(game.group_key());
+        // 쓰다가 죽어도 깨진 파일이 남지 않도록 임시 파일에 쓰고 바꿔치기
+        let tmp_path = path.with_extension("tmp");
+
+        let write = async {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            tokio::fs::write(&tmp_path, json).await?;
+            tokio::fs::rename(&tmp_path, &path).await
+        };
+        write.await.map_err(|e| Error::GameStoreFail(e.to_string()))
+    }
+
+    async fn remove(&self, group_key: &GroupKey) -> Result<()> {
+        match tokio::fs::remove_file(self.path(group_key)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(Error::GameStoreFail(e.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    async fn load(&self, group_key: &GroupKey) -> Result<Option<Game>> {
+        match tokio::fs::read_to_string(self.path(group_key)).await {
+            Ok(json) => Ok(from_json(group_key, &json)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::GameStoreFail(e.to_string())),
+        }
+    }
+
+    async fn load_all(&self) -> Result<Vec<Game>> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(Error::GameStoreFail(e.to_string())),
+        };
+
+        let mut games = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(|e| Error::GameStoreFail(e.to_string()))? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(SNAPSHOT_FILE_EXT) {
+                continue;
+            }
+            let json = tokio::fs::read_to_string(&path).await
+                .map_err(|e| Error::GameStoreFail(e.to_string()))?;
+            games.extend(from_json(&path.to_string_lossy(), &json));
+        }
+
+        Ok(games)
+    }
+
+    async fn try_lock(&self, group_key: &GroupKey, token: &str, ttl: Duration) -> Result<bool> {
+        let path = self.lock_path(group_key);
+        tokio::fs::create_dir_all(&self.dir).await
+            .map_err(|e| Error::GameStoreFail(e.to_string()))?;
+
+        // 잡은 채로 죽은 잠금은 치운다
+        if let Ok(metadata) = tokio::fs::metadata(&path).await {
+            let age = metadata.modified().ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+            if age.is_some_and(|age| age >= ttl) {
+                let _ = tokio::fs::remove_file(&path).await;
+            }
+        }
+
+        let created = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await;
+        match created {
+            Ok(mut file) => {
+                tokio::io::AsyncWriteExt::write_all(&mut file, token.as_bytes()).await
+                    .map_err(|e| Error::GameStoreFail(e.to_string()))?;
+                Ok(true)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(Error::GameStoreFail(e.to_string())),
+        }
+    }
+
+    async fn unlock(&self, group_key: &GroupKey, token: &str) -> Result<()> {
+        let path = self.lock_path(group_key);
+        match tokio::fs::read_to_string(&path).await {
+            Ok(owner) if owner == token => tokio::fs::remove_file(&path).await
+                .map_err(|e| Error::GameStoreFail(e.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    async fn claim_round(&self, group_key: &GroupKey, round_id: u64, user_id: &str) -> Result<RoundClaim> {
+        let path = self.claim_path(group_key, round_id);
+        tokio::fs::create_dir_all(&self.dir).await
+            .map_err(|e| Error::GameStoreFail(e.to_string()))?;
+
+        let created = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await;
+        match created {
+            Ok(mut file) => {
+                tokio::io::AsyncWriteExt::write_all(&mut file, user_id.as_bytes()).await
+                    .map_err(|e| Error::GameStoreFail(e.to_string()))?;
+                // 지난 문제의 기록은 이제 필요 없다
+                let _ = tokio::fs::remove_file(self.claim_path(group_key, round_id.wrapping_sub(1))).await;
+                Ok(RoundClaim::Won)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let winner = tokio::fs::read_to_string(&path).await
+                    .map_err(|e| Error::GameStoreFail(e.to_string()))?;
+                Ok(round_claim(user_id, winner))
+            }
+            Err(e) => Err(Error::GameStoreFail(e.to_string())),
+        }
+    }
+}
+
+/// 재시작하면 사라진다. 로컬 개발, 테스트용
+///
+/// 한 프로세스 안에서 `GameManager` 여러 개가 같이 쓰면 인스턴스 여러 개를 띄운 것처럼 동작한다.
+#[derive(Default)]
+pub struct InMemoryGameStore {
+    // 직렬화까지 거치도록 JSON으로 들고 있는다. group_key -> (version, JSON)
+    snapshots: Mutex<HashMap<GroupKey, (u64, String)>>,
+    // group_key -> (token, 만료 시각)
+    locks: Mutex<HashMap<GroupKey, (String, Instant)>>,
+    // group_key -> (마지막으로 맞힌 round_id, 맞힌 사람)
+    claims: Mutex<HashMap<GroupKey, (u64, String)>>,
+}
+
+#[async_trait]
+impl GameStore for InMemoryGameStore {
+    async fn save(&self, game: &Game) -> Result<()> {
+        let json = to_json(game)?;
+        let mut snapshots = self.snapshots.lock().unwrap();
+        check_version(game, snapshots.get(game.group_key()).map(|(version, _)| *version))?;
+        snapshots.insert(game.group_key().clone(), (game.version, json));
+        Ok(())
+    }
+
+    async fn remove(&self, group_key: &GroupKey) -> Result<()> {
+        self.snapshots.lock().unwrap().remove(group_key);
+        Ok(())
+    }
+
+    async fn load(&self, group_key: &GroupKey) -> Result<Option<Game>> {
+        Ok(self.snapshots.lock().unwrap().get(group_key)
+            .and_then(|(_, json)| from_json(group_key, json)))
+    }
+
+    async fn load_all(&self) -> Result<Vec<Game>> {
+        Ok(self.snapshots.lock().unwrap().iter()
+            .filter_map(|(group_key, (_, json))| from_json(group_key, json))
+            .collect())
+    }
+
+    async fn try_lock(&self, group_key: &GroupKey, token: &str, ttl: Duration) -> Result<bool> {
+        let mut locks = self.locks.lock().unwrap();
+        let now = Instant::now();
+        if locks.get(group_key).is_some_and(|(_, expires_at)| *expires_at > now) {
+            return Ok(false);
+        }
+        locks.insert(group_key.clone(), (token.to_string(), now + ttl));
+        Ok(true)
+    }
+
+    async fn unlock(&self, group_key: &GroupKey, token: &str) -> Result<()> {
+        let mut locks = self.locks.lock().unwrap();
+        if locks.get(group_key).is_some_and(|(owner, _)| owner == token) {
+            locks.remove(group_key);
+        }
+        Ok(())
+    }
+
+    async fn claim_round(&self, group_key: &GroupKey, round_id: u64, user_id: &str) -> Result<RoundClaim> {
+        let mut claims = self.claims.lock().unwrap();
+        match claims.get(group_key) {
+            Some((claimed_round, winner)) if *claimed_round == round_id => Ok(round_claim(user_id, winner.clone())),
+            _ => {
+                claims.insert(group_key.clone(), (round_id, user_id.to_string()));
+                Ok(RoundClaim::Won)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::state::tests::math_options;
+
+    fn game(group_key: &str, version: u64) -> Game {
+        let mut game = Game::new(group_key.to_string(), math_options());
+        game.version = version;
+        game
+    }
+
+    #[tokio::test]
+    async fn in_memory_save_load_remove() {
+        let store = InMemoryGameStore::default();
+        let group_key = "room".to_string();
+        assert!(store.load(&group_key).await.unwrap().is_none());
+
+        let saved = game(&group_key, 1);
+        store.save(&saved).await.unwrap();
+        let loaded = store.load(&group_key).await.unwrap().unwrap();
+        assert_eq!(loaded.version, 1);
+        assert_eq!(loaded.group_key(), &group_key);
+        assert_eq!(loaded.current_quiz.as_question().question_id(), saved.current_quiz.as_question().question_id());
+
+        store.save(&game("other", 1)).await.unwrap();
+        assert_eq!(store.load_all().await.unwrap().len(), 2);
+
+        store.remove(&group_key).await.unwrap();
+        assert!(store.load(&group_key).await.unwrap().is_none());
+        assert_eq!(store.load_all().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn in_memory_rejects_older_version() {
+        let store = InMemoryGameStore::default();
+        store.save(&game("room", 2)).await.unwrap();
+
+        assert!(matches!(store.save(&game("room", 2)).await, Err(Error::GameBusy(_))));
+        assert!(matches!(store.save(&game("room", 1)).await, Err(Error::GameBusy(_))));
+        store.save(&game("room", 3)).await.unwrap();
+        assert_eq!(store.load(&"room".to_string()).await.unwrap().unwrap().version, 3);
+
+        // 지우면 새 게임은 처음부터
+        store.remove(&"room".to_string()).await.unwrap();
+        store.save(&game("room", 1)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn file_rejects_older_version() {
+        let dir = std::env::temp_dir().join(format!("quizbot-store-{:016x}", rand::random::<u64>()));
+        let store = FileGameStore::new(dir.clone());
+        store.save(&game("room", 2)).await.unwrap();
+
+        assert!(matches!(store.save(&game("room", 1)).await, Err(Error::GameBusy(_))));
+        store.save(&game("room", 3)).await.unwrap();
+        assert_eq!(store.load_all().await.unwrap()[0].version, 3);
+
+        let _ = tokio::fs::remove_dir_all(dir).await;
+    }
+}
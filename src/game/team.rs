@@ -0,0 +1,216 @@
+//! 팀전: 한 방 안에서 A팀/B팀으로 나눠 점수를 겨룬다
+//!
+//! "팀 A", "팀 B"로 직접 고르거나, 팀 없이 답하면 인원이 적은 팀으로 자동 배정된다.
+//! 한 번 답하면 팀을 바꿀 수 없다 (얻은 점수가 예전 팀에 남으므로).
+//! 팀 정보는 `Game`에 들어 있어서 게임이 끝나면 같이 사라진다.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+use crate::skill::Mentions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Team {
+    A,
+    B,
+}
+
+impl Team {
+    pub const ALL: [Team; 2] = [Team::A, Team::B];
+
+    /// "A", "a", "A팀"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim_end_matches('팀').to_uppercase().as_str() {
+            "A" => Some(Team::A),
+            "B" => Some(Team::B),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Team::A => "A팀",
+            Team::B => "B팀",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Teams {
+    // user_id -> team
+    members: HashMap<String, Team>,
+    scores: HashMap<Team, u32>,
+    // 한 번이라도 답한 사람. 팀을 바꿀 수 없다
+    #[serde(default)]
+    answered: HashSet<String>,
+}
+
+impl Teams {
+    pub fn team_of(&self, user_id: &str) -> Option<Team> {
+        self.members.get(user_id).copied()
+    }
+
+    pub fn member_count(&self, team: Team) -> usize {
+        self.members.values().filter(|t| **t == team).count()
+    }
+
+    pub fn score(&self, team: Team) -> u32 {
+        self.scores.get(&team).copied().unwrap_or(0)
+    }
+
+    /// 팀을 고르지 않으면 지금 팀 그대로, 팀이 없으면 인원이 적은 팀으로.
+    /// 이미 답한 사람은 다른 팀으로 옮길 수 없다
+    pub fn join(&mut self, user_id: &str, team: Option<Team>) -> Result<Team> {
+        let current = self.team_of(user_id);
+        let team = team.or(current).unwrap_or_else(|| self.smaller_team());
+        if let Some(current) = current.filter(|current| *current != team && self.answered.contains(user_id)) {
+            return Err(Error::GameTeamLocked(current));
+        }
+
+        self.members.insert(user_id.to_string(), team);
+        Ok(team)
+    }
+
+    /// 답할 때. 팀이 없으면 자동 배정하고, 이제부터 팀을 바꿀 수 없다
+    pub fn ensure_member(&mut self, user_id: &str) -> Team {
+        let team = match self.team_of(user_id) {
+            Some(team) => team,
+            None => {
+                let team = self.smaller_team();
+                self.members.insert(user_id.to_string(), team);
+                team
+            }
+        };
+        self.answered.insert(user_id.to_string());
+        team
+    }
+
+    pub fn add_point(&mut self, team: Team) {
+        *self.scores.entry(team).or_insert(0) += 1;
+    }
+
+    fn smaller_team(&self) -> Team {
+        Team::ALL.into_iter()
+            .min_by_key(|team| self.member_count(*team))
+            .unwrap()
+    }
+
+    pub fn summary(&self) -> TeamSummary {
+        let scores: Vec<(Team, u32)> = Team::ALL.into_iter()
+            .map(|team| (team, self.score(team)))
+            .collect();
+
+        let winner = match (self.score(Team::A), self.score(Team::B)) {
+            (a, b) if a > b => Some(Team::A),
+            (a, b) if a < b => Some(Team::B),
+            _ => None,
+        };
+
+        let mut winner_members: Vec<String> = winner
+            .map(|winner| self.members.iter()
+                .filter(|(_, team)| **team == winner)
+                .map(|(user_id, _)| user_id.clone())
+                .collect())
+            .unwrap_or_default();
+        winner_members.sort();
+
+        TeamSummary {
+            scores,
+            winner,
+            winner_members,
+        }
+    }
+}
+
+/// 게임이 끝났을 때 팀 결과. 비기면 winner가 None
+#[derive(Debug, Clone)]
+pub struct TeamSummary {
+    pub scores: Vec<(Team, u32)>,
+    pub winner: Option<Team>,
+    pub winner_members: Vec<String>,
+}
+
+impl TeamSummary {
+    /// 이긴 팀원은 `mentions`에 멘션으로 넣는다
+    pub fn message(&self, mentions: &mut Mentions) -> String {
+        let score_line = self.scores.iter()
+            .map(|(team, score)| format!("{} {}점", team.name(), score))
+            .collect::<Vec<_>>()
+            .join(" : ");
+
+        match self.winner {
+            Some(winner) => {
+                let members = self.winner_members.iter()
+                    .map(|user_id| mentions.placeholder(user_id))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!("🚩 {} 승리! ({})\n{}", winner.name(), score_line, members)
+            }
+            None => format!("🤝 무승부! ({})", score_line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_assignment_keeps_teams_even() {
+        let mut teams = Teams::default();
+        for user_id in ["a", "b", "c", "d", "e"] {
+            teams.join(user_id, None).unwrap();
+        }
+        assert_eq!((teams.member_count(Team::A), teams.member_count(Team::B)), (3, 2));
+
+        // 직접 고른 사람이 있으면 적은 쪽으로
+        teams.join("f", Some(Team::A)).unwrap();
+        assert_eq!(teams.ensure_member("g"), Team::B);
+        assert_eq!((teams.member_count(Team::A), teams.member_count(Team::B)), (4, 3));
+    }
+
+    #[test]
+    fn team_is_locked_after_answering() {
+        let mut teams = Teams::default();
+        assert_eq!(teams.join("alice", Some(Team::A)).unwrap(), Team::A);
+        // 답하기 전에는 바꿀 수 있다
+        assert_eq!(teams.join("alice", Some(Team::B)).unwrap(), Team::B);
+        // 팀 없이 다시 부르면 그대로
+        assert_eq!(teams.join("alice", None).unwrap(), Team::B);
+
+        let team = teams.ensure_member("alice");
+        teams.add_point(team);
+        assert!(matches!(teams.join("alice", Some(Team::A)), Err(Error::GameTeamLocked(Team::B))));
+        assert_eq!(teams.join("alice", Some(Team::B)).unwrap(), Team::B);
+        assert_eq!((teams.score(Team::A), teams.score(Team::B)), (0, 1));
+        assert_eq!(teams.team_of("alice"), Some(Team::B));
+    }
+
+    #[test]
+    fn summary_names_the_winning_team() {
+        let mut teams = Teams::default();
+        for (user_id, team) in [("carol", Team::B), ("alice", Team::B), ("bob", Team::A)] {
+            teams.join(user_id, Some(team)).unwrap();
+        }
+        teams.add_point(Team::B);
+        teams.add_point(Team::B);
+        teams.add_point(Team::A);
+
+        let summary = teams.summary();
+        assert_eq!(summary.scores, vec![(Team::A, 1), (Team::B, 2)]);
+        assert_eq!(summary.winner, Some(Team::B));
+        assert_eq!(summary.winner_members, vec!["alice".to_string(), "carol".to_string()]);
+        assert_eq!(
+            summary.message(&mut Mentions::default()),
+            "🚩 B팀 승리! (A팀 1점 : B팀 2점)\n{{#mentions.user1}} {{#mentions.user2}}",
+        );
+
+        teams.add_point(Team::A);
+        let summary = teams.summary();
+        assert_eq!(summary.winner, None);
+        assert!(summary.winner_members.is_empty());
+        assert_eq!(summary.message(&mut Mentions::default()), "🤝 무승부! (A팀 2점 : B팀 2점)");
+    }
+}
@@ -1,13 +1,19 @@
+use std::sync::Arc;
+
 use axum::{middleware, Router};
 use bb8::Pool;
 use bb8_redis::RedisConnectionManager;
-use redis::AsyncCommands;
 use tracing::debug;
 use tracing_subscriber::EnvFilter;
 
 use crate::config::config;
-use crate::game::db::{flag_quiz_db, quiz_db};
+use crate::game::db::{flag_quiz_db, image_quiz_db, quiz_db};
+use crate::clock::SystemClock;
+use crate::event::event_client;
 use crate::game::state::GameManager;
+use crate::game::daily::daily_store;
+use crate::game::score::score_store;
+use crate::game::store::game_store;
 
 pub use self::error::{Error, Result};
 
@@ -17,6 +23,8 @@ mod config;
 mod game;
 mod conn;
 mod skill;
+mod event;
+mod clock;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -27,6 +35,7 @@ async fn main() -> Result<()> {
     debug!("{:<12} - initializing quiz local db", "MAIN");
     quiz_db();
     flag_quiz_db();
+    image_quiz_db();
 
     debug!("{:<12} - connecting to redis", "MAIN");
     let manager = RedisConnectionManager::new(config().REDIS_CONNECTION_STRING.as_str()).unwrap();
@@ -40,11 +49,24 @@ async fn main() -> Result<()> {
     // }
     // debug!("{:<12} - successfully connected to redis and pinged it", "MAIN");
 
-    let gm = GameManager::new(pool.clone()).unwrap();
-    let app = Router::new()
-        .merge(web::routes_bot_request::routes(gm))
+    let store = game_store(&config().GAME_STORE, pool.clone());
+    let scores = score_store(&config().GAME_STORE, pool.clone());
+    let daily = daily_store(&config().GAME_STORE, pool.clone());
+    let gm = GameManager::new(pool.clone(), event_client(config()), Arc::new(SystemClock), store, scores, daily, config().DISTRIBUTED).unwrap();
+    gm.restore_games().await?;
+    gm.stats().spawn_refresh_task();
+    gm.spawn_reaper();
+    let mut app = Router::new()
+        .merge(web::routes_bot_request::routes(gm.clone()))
+        .merge(web::routes_admin::routes(gm))
         .layer(middleware::from_fn(web::mw_auth::mw_header_checker));
 
+    // static files - no auth
+    if let Some(static_files_dir) = &config().STATIC_FILES_DIR {
+        debug!("{:<12} - serving static files from {}", "MAIN", static_files_dir);
+        app = app.merge(web::routes_static::routes(static_files_dir));
+    }
+
     // let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await.unwrap();
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
     // let listener = tokio::net::TcpListener::bind("0.0.0.0:80").await.unwrap();
@@ -1,7 +1,27 @@
 use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
 use kakao_rs::prelude::Template;
 use serde::{Deserialize, Serialize};
 
+use crate::{Error, Result};
+
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(5);
+const MENTION_KEY_PREFIX: &str = "user";
+// 텍스트 안의 멘션: `{{#mentions.key}}`
+pub const MENTION_PLACEHOLDER_PREFIX: &str = "{{#mentions.";
+pub const MENTION_PLACEHOLDER_SUFFIX: &str = "}}";
+const ZERO_WIDTH_SPACE: char = '\u{200B}';
+
+/// 스킬 응답. 콜백을 쓰면 일단 `Callback`으로 답하고 결과는 `send_callback`으로
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum SkillResponse {
+    Template(TemplateWithExtra),
+    Callback(CallbackAck),
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TemplateWithExtra {
     #[serde(flatten)]
@@ -9,6 +29,66 @@ pub struct TemplateWithExtra {
     
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extra: Option<Extra>,
+
+    // 다음 발화로 이어지는 대화 상태
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<ContextControl>,
+}
+
+/// 요청에 들어오는 context. 블록에 입력 context로 등록된 것만 온다
+/// {"name": "...", "lifespan": 1, "ttl": 60, "params": {"key": {"value": "...", "resolvedValue": "..."}}}
+#[derive(Deserialize, Debug)]
+pub struct RequestContext {
+    pub name: String,
+    #[serde(default)]
+    pub params: HashMap<String, ContextParam>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextParam {
+    pub value: String,
+    pub resolved_value: Option<String>,
+}
+
+impl RequestContext {
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params.get(key)
+            .map(|param| param.resolved_value.as_deref().unwrap_or(&param.value))
+    }
+}
+
+/// 응답에 싣는 context. 켜고(`lifeSpan` > 0) 끌(0) 수 있다
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ContextControl {
+    pub values: Vec<ContextValue>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextValue {
+    pub name: String,
+    // 몇 번의 발화 동안 유지할지
+    pub life_span: u32,
+    // 초. 없으면 기본 (10분)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub params: HashMap<String, String>,
+}
+
+impl ContextControl {
+    /// context 하나만 켠다
+    pub fn single(name: &str, life_span: u32, ttl: u32, params: HashMap<String, String>) -> Self {
+        Self {
+            values: vec![ContextValue {
+                name: name.to_string(),
+                life_span,
+                ttl: Some(ttl),
+                params,
+            }],
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -23,3 +103,128 @@ pub struct Mention {
     pub mention_type: String,  // botUserKey
     pub id: String,
 }
+
+/// 한 응답에 들어가는 멘션. 사람마다 키(`user1`, `user2`, ...)를 붙이고
+/// 텍스트에 넣을 `{{#mentions.key}}`를 돌려준다. 같은 사람은 같은 키를 쓴다
+#[derive(Default, Debug)]
+pub struct Mentions {
+    // (mention key, user_id)
+    keys: Vec<(String, String)>,
+}
+
+impl Mentions {
+    pub fn placeholder(&mut self, user_id: &str) -> String {
+        let key = match self.keys.iter().find(|(_, id)| id == user_id) {
+            Some((key, _)) => key.clone(),
+            None => {
+                let key = format!("{}{}", MENTION_KEY_PREFIX, self.keys.len() + 1);
+                self.keys.push((key.clone(), user_id.to_string()));
+                key
+            }
+        };
+        Self::placeholder_of(&key)
+    }
+
+    fn placeholder_of(key: &str) -> String {
+        format!("{}{}{}", MENTION_PLACEHOLDER_PREFIX, key, MENTION_PLACEHOLDER_SUFFIX)
+    }
+
+    /// `keep(placeholder)`가 false인 멘션은 뺀다 (텍스트에 남지 않은 멘션)
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        self.keys.retain(|(key, _)| keep(&Self::placeholder_of(key)));
+    }
+
+    /// 멘션을 쓸 수 없는 곳(Event API 등)으로 보낼 때. `{{#mentions.key}}`를 이름으로 바꾼다
+    pub fn to_plain_text(&self, text: &str, name: impl Fn(&str) -> String) -> String {
+        self.keys.iter().fold(text.to_string(), |text, (key, user_id)| {
+            text.replace(&Self::placeholder_of(key), &escape_mentions(&name(user_id)))
+        })
+    }
+
+    /// 멘션이 없으면 None
+    pub fn into_extra(self) -> Option<Extra> {
+        (!self.keys.is_empty()).then(|| Extra {
+            mentions: self.keys.into_iter()
+                .map(|(key, user_id)| (key, Mention {
+                    mention_type: "botUserKey".to_string(),
+                    id: user_id,
+                }))
+                .collect(),
+        })
+    }
+}
+
+/// 사용자나 문제에서 온 글자가 멘션으로 바뀌지 않도록 `{{` 사이에 폭 없는 공백을 넣는다
+pub fn escape_mentions(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut prev = None;
+    for c in text.chars() {
+        if prev == Some('{') && c == '{' {
+            escaped.push(ZERO_WIDTH_SPACE);
+        }
+        escaped.push(c);
+        prev = Some(c);
+    }
+    escaped
+}
+
+/// {"version": "2.0", "useCallback": true, "data": {"text": "..."}}
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CallbackAck {
+    version: String,
+    use_callback: bool,
+    data: CallbackData,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CallbackData {
+    // 콜백 블록에서 `{{#webhook.text}}`로 보여줄 수 있다
+    text: String,
+}
+
+impl CallbackAck {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            version: "2.0".to_string(),
+            use_callback: true,
+            data: CallbackData { text: text.into() },
+        }
+    }
+}
+
+// {"taskId": "...", "status": "SUCCESS"}
+#[derive(Deserialize)]
+struct CallbackResult {
+    status: String,
+    message: Option<String>,
+}
+
+fn http_client() -> &'static reqwest::Client {
+    static INSTANCE: OnceLock<reqwest::Client> = OnceLock::new();
+
+    INSTANCE.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(CALLBACK_TIMEOUT)
+            .build()
+            .expect("failed to build http client")
+    })
+}
+
+/// `callbackUrl`로 최종 응답을 보낸다. url은 1분 안에 한 번만 쓸 수 있어서 재시도하지 않는다
+pub async fn send_callback(callback_url: &str, response: &TemplateWithExtra) -> Result<()> {
+    let result = http_client().post(callback_url)
+        .json(response)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| Error::CallbackSendFail(e.to_string()))?;
+
+    let result: CallbackResult = result.json()
+        .await
+        .map_err(|e| Error::CallbackSendFail(e.to_string()))?;
+    if result.status != "SUCCESS" {
+        return Err(Error::CallbackSendFail(format!("{}: {}", result.status, result.message.unwrap_or_default())));
+    }
+    Ok(())
+}
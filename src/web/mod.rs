@@ -1,3 +1,6 @@
 pub mod model;
+pub mod response;
 pub mod mw_auth;
+pub mod routes_admin;
 pub mod routes_bot_request;
+pub mod routes_static;
@@ -1,5 +1,33 @@
 use serde::{Deserialize, Serialize};
-use crate::game::db::QUIZ_CATEGORIES;
+use crate::game::challenge::is_challenge_code;
+use crate::game::db::{is_valid_category, CAPITAL_CATEGORY, CAPITAL_REVERSE_CATEGORY, CHOSUNG_CATEGORY, FLAG_CATEGORY, FLAG_CHOICE_CATEGORY, MATH_CATEGORY};
+use crate::game::mode::GameMode;
+use crate::game::model::DifficultyMode;
+use crate::game::state::StartOptions;
+use crate::game::team::Team;
+use crate::skill::RequestContext;
+
+const TEAM_BATTLE_NAME: &str = "팀전";
+const LOBBY_NAME: &str = "대기실";
+
+// 카테고리 없이 "시작"하면 카테고리를 물어보고, 이 context가 살아 있는 동안 다음 발화를 카테고리로 받는다
+pub const START_CATEGORY_CONTEXT: &str = "start_category";
+// 카테고리 말고 같이 입력한 옵션 (ex. "서바이벌 팀전")
+pub const START_OPTIONS_PARAM: &str = "options";
+pub const START_CATEGORY_CONTEXT_TTL_SECS: u32 = 60;
+pub const ALL_CATEGORIES_NAME: &str = "전체";
+// quickReplies는 10개까지
+pub const START_CATEGORY_CHOICES: [&str; 9] = [
+    ALL_CATEGORIES_NAME,
+    FLAG_CATEGORY,
+    "상식",
+    "고사성어",
+    CHOSUNG_CATEGORY,
+    CAPITAL_CATEGORY,
+    CAPITAL_REVERSE_CATEGORY,
+    FLAG_CHOICE_CATEGORY,
+    MATH_CATEGORY,
+];
 
 /// bot proxy -> skill server payload
 /// skipped unused fields
@@ -7,6 +35,8 @@ use crate::game::db::QUIZ_CATEGORIES;
 #[serde(rename_all = "camelCase")]
 pub struct BotRequest {
     pub user_request: UserRequest,
+    #[serde(default)]
+    pub contexts: Vec<RequestContext>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -14,6 +44,9 @@ pub struct UserRequest {
     pub user: User,
     pub chat: Chat,
     pub utterance: String,
+    // 블록에서 콜백을 켰을 때만 온다
+    #[serde(rename = "callbackUrl", default)]
+    pub callback_url: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -37,42 +70,156 @@ pub enum ChatIdType {
 }
 
 pub enum Command {
-    Start(Option<String>),
+    // 카테고리 없이 시작했을 때. 카테고리를 물어본다 (같이 입력한 옵션)
+    AskCategory(String),
+    Start(StartOptions),
     Stop,
     Answer(String),
+    Skip,
+    Hint,
     Ranking,
+    JoinTeam(Option<Team>),  // None이면 자동 배정
+    Challenge(ChallengeCommand),
+    DailyStart,
+    DailyAnswer(String),
+    DailyResult,
+    JoinLobby,
+    StartLobby,
+    Pause,
+    Resume,
+    SetNickname(String),
+    Profile,
+    Records,
+}
+
+pub enum ChallengeCommand {
+    Create(StartOptions),
+    Accept(String),  // code
 }
 
 impl Command {
+    /// 5초 안에 답하기 어려울 수 있는 명령. 콜백을 쓸 수 있으면 결과는 콜백으로 보낸다
+    pub fn is_slow(&self) -> bool {
+        matches!(self, Command::DailyResult | Command::Ranking)
+    }
+
     pub fn from_utterance(utterance: &str) -> Option<Command> {
         let utterance = utterance.trim();
-        let command = utterance.splitn(2, ' ').next()?;
+        let (command, args) = match utterance.split_once(' ') {
+            Some((command, args)) => (command, Some(args.trim())),
+            None => (utterance, None),
+        };
 
         match command {
             "시작" => {
-                // 카테고리는 있을 수도 있고 없을 수도 있다
-                let category = utterance.splitn(2, ' ')
-                    .nth(1)
-                    .map(|s| s.to_string());
-
-                // 유효하지 않은 카테고리
-                // "국기"는 OK
-                if let Some(ref category) = category {
-                    if !QUIZ_CATEGORIES.contains(category.as_str()) && category != "국기" {
-                        return None;
-                    }
+                let args = args.unwrap_or_default();
+                let options = Self::parse_start_options(args)?;
+                match options.category {
+                    Some(_) => Some(Command::Start(options)),
+                    None => Some(Command::AskCategory(args.to_string())),
                 }
-
-                Some(Command::Start(category))
             }
             "중지" | "중단" | "정지" | "종료" | "그만" | "멈춰" => Some(Command::Stop),
             // TODO: "정답" 명령어를 사용하지 않고, 바로 답 입력하도록 ?
             "정답" => {
-                let answer = utterance.splitn(2, ' ').nth(1)?;
+                let answer = args?;
                 Some(Command::Answer(answer.to_string()))
             }
+            "패스" | "스킵" => Some(Command::Skip),
+            "힌트" => Some(Command::Hint),
             "랭킹" | "순위" => Some(Command::Ranking),
+            "오늘의" => match args {
+                Some("퀴즈") => Some(Command::DailyStart),
+                Some("퀴즈 결과") => Some(Command::DailyResult),
+                _ => None,
+            },
+            "오늘" => args.map(|answer| Command::DailyAnswer(answer.to_string())),
+            "도전" => Self::parse_challenge(args.unwrap_or_default()).map(Command::Challenge),
+            "일시정지" => Some(Command::Pause),
+            "재개" => Some(Command::Resume),
+            "참가" => Some(Command::JoinLobby),
+            "출발" => Some(Command::StartLobby),
+            "닉네임" => args.map(|nickname| Command::SetNickname(nickname.to_string())),
+            "내정보" => Some(Command::Profile),
+            "내기록" => Some(Command::Records),
+            "내" => match args {
+                Some("정보") => Some(Command::Profile),
+                Some("기록") => Some(Command::Records),
+                _ => None,
+            },
+            "팀" => match args {
+                Some(team) => Team::from_name(team).map(|team| Command::JoinTeam(Some(team))),
+                None => Some(Command::JoinTeam(None)),
+            },
             _ => None,
         }
     }
+
+    /// 명령이 아닌 발화를 살아 있는 context에 따라 해석한다 ("어떤 카테고리?"에 대한 답 등)
+    pub fn from_context(contexts: &[RequestContext], utterance: &str) -> Option<Command> {
+        let context = contexts.iter().find(|context| context.name == START_CATEGORY_CONTEXT)?;
+        let options = context.param(START_OPTIONS_PARAM).unwrap_or_default();
+
+        let args = match utterance.trim() {
+            ALL_CATEGORIES_NAME => options.to_string(),
+            category => format!("{} {}", category, options),
+        };
+        Self::parse_start_options(&args).map(Command::Start)
+    }
+
+    /// "도전 [카테고리] [N문제]"로 만들고, "도전 {코드}"로 받는다
+    fn parse_challenge(args: &str) -> Option<ChallengeCommand> {
+        if is_challenge_code(args) && !is_valid_category(args) {
+            return Some(ChallengeCommand::Accept(args.to_uppercase()));
+        }
+
+        Self::parse_start_options(args).map(ChallengeCommand::Create)
+    }
+
+    /// "상식 어려움", "초성 국기", "수학 적응형", "국기 서바이벌", "상식 팀전 대기실" 등
+    /// 난이도/모드/팀전/대기실은 아무 위치에나 올 수 있고, 나머지 단어가 카테고리
+    fn parse_start_options(args: &str) -> Option<StartOptions> {
+        let mut options = StartOptions::default();
+        let mut category_words: Vec<&str> = Vec::new();
+        let mut mode: Option<GameMode> = None;
+
+        for word in args.split_whitespace() {
+            if word == TEAM_BATTLE_NAME {
+                options.team_battle = true;
+                continue;
+            }
+            if word == LOBBY_NAME {
+                options.lobby = true;
+                continue;
+            }
+            if options.difficulty.is_none() {
+                if let Some(difficulty) = DifficultyMode::from_name(word) {
+                    options.difficulty = Some(difficulty);
+                    continue;
+                }
+            }
+            if mode.is_none() {
+                if let Some(game_mode) = GameMode::from_name(word) {
+                    mode = Some(game_mode);
+                    continue;
+                }
+            }
+            category_words.push(word);
+        }
+        options.mode = mode.unwrap_or_default();
+
+        // 카테고리는 있을 수도 있고 없을 수도 있다
+        if !category_words.is_empty() {
+            let category = category_words.join(" ");
+
+            // 유효하지 않은 카테고리
+            // "국기", "초성", "초성 상식" 등은 OK
+            if !is_valid_category(&category) {
+                return None;
+            }
+            options.category = Some(category);
+        }
+
+        Some(options)
+    }
 }
@@ -0,0 +1,273 @@
+//! 스킬 응답 조립
+//!
+//! outputs, quickReplies, mentions, context를 모아서 `TemplateWithExtra`로 만든다.
+//! 카카오 제한(outputs 3개, 텍스트 1000자, quickReplies 10개, 라벨 14자)은 여기서 맞춘다.
+//! outputs가 넘치면 붙어 있는 텍스트끼리 합치고, 그래도 넘치면 우선순위(`Priority`)가 낮은 것부터 뺀다.
+//! 긴 텍스트는 멘션 중간을 피해서 자르고, 빠지거나 잘려서 안 보이는 멘션은 extra에서도 뺀다.
+//!
+//! https://i.kakao.com/docs/skill-response-format
+
+use std::cmp::Reverse;
+
+use kakao_rs::prelude::{QuickReply, SimpleImage, SimpleText, Template, Types};
+use tracing::warn;
+
+use crate::skill::{ContextControl, Mentions, TemplateWithExtra, MENTION_PLACEHOLDER_PREFIX, MENTION_PLACEHOLDER_SUFFIX};
+
+pub const MAX_OUTPUTS: usize = 3;
+pub const MAX_TEXT_CHARS: usize = 1000;
+pub const MAX_QUICK_REPLIES: usize = 10;
+pub const MAX_QUICK_REPLY_LABEL_CHARS: usize = 14;
+
+const TEXT_MERGE_SEPARATOR: &str = "\n\n";
+const ELLIPSIS: char = '…';
+
+enum Output {
+    // 합치거나 자를 수 있도록 build 전까지 문자열로 들고 있는다
+    Text(String),
+    Other(Box<Types>),
+}
+
+/// outputs가 넘칠 때 낮은 것부터 뺀다. 같으면 뒤에 있는 것부터
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    // 없어도 되는 것 (곁들이는 이미지 등)
+    Low,
+    #[default]
+    Normal,
+    // 문제처럼 꼭 보여야 하는 것
+    High,
+}
+
+#[derive(Default)]
+pub struct ResponseBuilder {
+    outputs: Vec<(Output, Priority)>,
+    // (label, messageText)
+    quick_replies: Vec<(String, String)>,
+    mentions: Mentions,
+    context: Option<ContextControl>,
+}
+
+impl ResponseBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(&mut self, text: impl Into<String>) -> &mut Self {
+        self.outputs.push((Output::Text(text.into()), Priority::default()));
+        self
+    }
+
+    pub fn image(&mut self, image_url: impl Into<String>, alt_text: impl Into<String>) -> &mut Self {
+        self.output(SimpleImage::new(image_url.into(), alt_text.into()).build())
+    }
+
+    /// 카드, 캐러셀 등
+    pub fn output(&mut self, output: Types) -> &mut Self {
+        self.outputs.push((Output::Other(Box::new(output)), Priority::default()));
+        self
+    }
+
+    /// 바로 앞에 넣은 output의 우선순위. ex) `response.text(question).priority(Priority::High)`
+    pub fn priority(&mut self, priority: Priority) -> &mut Self {
+        if let Some((_, last)) = self.outputs.last_mut() {
+            *last = priority;
+        }
+        self
+    }
+
+    pub fn quick_reply(&mut self, label: impl Into<String>, message_text: impl Into<String>) -> &mut Self {
+        self.quick_replies.push((label.into(), message_text.into()));
+        self
+    }
+
+    /// 텍스트에 넣을 `{{#mentions.key}}`. 키는 알아서 붙는다
+    pub fn mention(&mut self, user_id: &str) -> String {
+        self.mentions.placeholder(user_id)
+    }
+
+    /// 여러 사람을 멘션하는 메시지를 만들 때 (`GameSummary::message` 등)
+    pub fn mentions(&mut self) -> &mut Mentions {
+        &mut self.mentions
+    }
+
+    pub fn context(&mut self, context: ContextControl) -> &mut Self {
+        self.context = Some(context);
+        self
+    }
+
+    pub fn build(mut self) -> TemplateWithExtra {
+        let mut template = Template::new();
+        // 빼거나 잘라서 없어진 멘션은 extra에서도 뺀다
+        let mut shown = Vec::new();
+        for output in fit_outputs(self.outputs) {
+            let output = match output {
+                Output::Text(text) => SimpleText::new(truncate_chars(&text, MAX_TEXT_CHARS)).build(),
+                Output::Other(output) => *output,
+            };
+            shown.push(serde_json::to_string(&output).unwrap_or_default());
+            template.add_output(output);
+        }
+        self.mentions.retain(|placeholder| shown.iter().any(|output| output.contains(placeholder)));
+
+        if self.quick_replies.len() > MAX_QUICK_REPLIES {
+            warn!("{:<12} - too many quick replies: {}", "RESPONSE", self.quick_replies.len());
+        }
+        for (label, message_text) in self.quick_replies.into_iter().take(MAX_QUICK_REPLIES) {
+            template.add_qr(QuickReply::new(truncate_chars(&label, MAX_QUICK_REPLY_LABEL_CHARS), message_text));
+        }
+
+        TemplateWithExtra {
+            template,
+            extra: self.mentions.into_extra(),
+            context: self.context,
+        }
+    }
+}
+
+// 넘치는 만큼 앞에서부터 붙어 있는 텍스트를 합친다. 합쳐서 너무 길어지면 합치지 않는다.
+// 그래도 넘치면 우선순위가 낮은 것부터 뺀다
+fn fit_outputs(outputs: Vec<(Output, Priority)>) -> Vec<Output> {
+    let mut excess = outputs.len().saturating_sub(MAX_OUTPUTS);
+    let mut fitted: Vec<(Output, Priority)> = Vec::with_capacity(outputs.len());
+
+    for (output, priority) in outputs {
+        if excess > 0 {
+            if let (Some((Output::Text(prev), prev_priority)), Output::Text(text)) = (fitted.last_mut(), &output) {
+                let merged_chars = prev.chars().count() + TEXT_MERGE_SEPARATOR.len() + text.chars().count();
+                if merged_chars <= MAX_TEXT_CHARS {
+                    prev.push_str(TEXT_MERGE_SEPARATOR);
+                    prev.push_str(text);
+                    *prev_priority = (*prev_priority).max(priority);
+                    excess -= 1;
+                    continue;
+                }
+            }
+        }
+        fitted.push((output, priority));
+    }
+
+    if fitted.len() > MAX_OUTPUTS {
+        warn!("{:<12} - too many outputs, dropped {}", "RESPONSE", fitted.len() - MAX_OUTPUTS);
+    }
+    while fitted.len() > MAX_OUTPUTS {
+        let (lowest, _) = fitted.iter()
+            .enumerate()
+            .min_by_key(|(i, (_, priority))| (*priority, Reverse(*i)))
+            .unwrap();
+        fitted.remove(lowest);
+    }
+    fitted.into_iter().map(|(output, _)| output).collect()
+}
+
+// 글자 수 기준. 잘랐으면 끝에 …. 멘션(`{{#mentions.key}}`) 중간이면 멘션 앞에서 자른다
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let mut end = text.char_indices().nth(max_chars - 1).map_or(text.len(), |(i, _)| i);
+    // 잘리는 자리 앞의 마지막 `{{`가 닫히지 않은 멘션이면
+    if let Some(start) = text[..end].rfind("{{") {
+        if text[start..].starts_with(MENTION_PLACEHOLDER_PREFIX) && !text[start..end].contains(MENTION_PLACEHOLDER_SUFFIX) {
+            end = start;
+        }
+    }
+    format!("{}{}", &text[..end], ELLIPSIS)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn build(response: ResponseBuilder) -> Value {
+        serde_json::to_value(response.build()).unwrap()
+    }
+
+    fn text(text: &str) -> Value {
+        json!({"simpleText": {"text": text}})
+    }
+
+    fn image(image_url: &str) -> Value {
+        json!({"simpleImage": {"imageUrl": image_url, "altText": "alt"}})
+    }
+
+    #[test]
+    fn merges_adjacent_texts() {
+        let mut response = ResponseBuilder::new();
+        response.text("a").text("b").image("https://img/1", "alt").text("c").text("d");
+
+        assert_eq!(build(response), json!({
+            "version": "2.0",
+            "template": {"outputs": [text("a\n\nb"), image("https://img/1"), text("c\n\nd")]},
+        }));
+    }
+
+    #[test]
+    fn drops_lower_priority_outputs_first() {
+        let mut response = ResponseBuilder::new();
+        response.image("https://img/1", "alt").priority(Priority::High);
+        response.image("https://img/2", "alt").priority(Priority::Low);
+        response.image("https://img/3", "alt");
+        response.image("https://img/4", "alt");
+        response.image("https://img/5", "alt").priority(Priority::High);
+
+        // Low 다음에 Normal 중 뒤에 있는 것부터
+        assert_eq!(build(response), json!({
+            "version": "2.0",
+            "template": {"outputs": [image("https://img/1"), image("https://img/3"), image("https://img/5")]},
+        }));
+    }
+
+    #[test]
+    fn truncates_long_text_outside_mentions() {
+        let mut response = ResponseBuilder::new();
+        let first = response.mention("first");
+        let second = response.mention("second");
+        // 두 번째 멘션 한가운데가 1000자째
+        let padding = "가".repeat(MAX_TEXT_CHARS - first.chars().count() - 10);
+        response.text(format!("{}{}{} 님", first, padding, second));
+
+        let expected = format!("{}{}{}", first, padding, ELLIPSIS);
+        // 잘려서 안 보이는 멘션은 extra에서도 빠진다
+        assert_eq!(build(response), json!({
+            "version": "2.0",
+            "template": {"outputs": [text(&expected)]},
+            "extra": {"mentions": {"user1": {"type": "botUserKey", "id": "first"}}},
+        }));
+    }
+
+    #[test]
+    fn removes_mentions_of_dropped_outputs() {
+        let mut response = ResponseBuilder::new();
+        let dropped = response.mention("dropped");
+        response.image("https://img/1", "alt").priority(Priority::High);
+        response.text(format!("{} 님", dropped)).priority(Priority::Low);
+        response.image("https://img/2", "alt").priority(Priority::High);
+        response.image("https://img/3", "alt").priority(Priority::High);
+
+        assert_eq!(build(response), json!({
+            "version": "2.0",
+            "template": {"outputs": [image("https://img/1"), image("https://img/2"), image("https://img/3")]},
+        }));
+    }
+
+    #[test]
+    fn caps_quick_replies_and_truncates_labels() {
+        let mut response = ResponseBuilder::new();
+        response.text("고르세요");
+        for i in 0..MAX_QUICK_REPLIES + 2 {
+            response.quick_reply(format!("{}번 아주아주아주아주 긴 라벨", i), format!("정답 {}", i));
+        }
+
+        let quick_replies: Vec<Value> = (0..MAX_QUICK_REPLIES)
+            .map(|i| json!({"action": "message", "label": format!("{}번 아주아주아주아주 긴…", i), "messageText": format!("정답 {}", i)}))
+            .collect();
+        assert_eq!(build(response), json!({
+            "version": "2.0",
+            "template": {"outputs": [text("고르세요")], "quickReplies": quick_replies},
+        }));
+    }
+}
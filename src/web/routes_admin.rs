@@ -0,0 +1,74 @@
+use axum::extract::{Path, State};
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use serde::Serialize;
+use tracing::debug;
+
+use crate::Result;
+use crate::game::profile::UserStats;
+use crate::game::state::{GameManager, GameManagerStatus};
+use crate::game::stats::QuestionStatsReport;
+
+// 봇 요청과 같은 인증 헤더 레이어 안쪽에 merge 할 것
+pub fn routes(
+    gm: GameManager,
+) -> Router {
+    Router::new()
+        .route("/admin/games", get(games_status))
+        .route("/admin/question-stats", get(question_stats))
+        .route("/admin/question-review/:question_id", delete(dismiss_question_review))
+        .route("/admin/users/:user_id/stats", get(user_stats))
+        .with_state(gm)
+}
+
+/// 진행 중인 게임 수, 자동 종료된 게임 수 등
+pub async fn games_status(
+    State(gm): State<GameManager>,
+) -> Json<GameManagerStatus> {
+    debug!("{:<12} - games_status", "HANDLER");
+
+    Json(gm.status().await)
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuestionStatsResponse {
+    // 출제 횟수 내림차순
+    pub questions: Vec<QuestionStatsReport>,
+    pub review: Vec<String>,
+}
+
+pub async fn question_stats(
+    State(gm): State<GameManager>,
+) -> Result<Json<QuestionStatsResponse>> {
+    debug!("{:<12} - question_stats", "HANDLER");
+
+    let mut all_stats = gm.stats().all_stats().await?;
+    all_stats.sort_by_key(|stats| std::cmp::Reverse(stats.asked));
+
+    let mut review = gm.stats().review_list().await?;
+    review.sort();
+
+    Ok(Json(QuestionStatsResponse {
+        questions: all_stats.into_iter().map(QuestionStatsReport::from).collect(),
+        review,
+    }))
+}
+
+pub async fn dismiss_question_review(
+    State(gm): State<GameManager>,
+    Path(question_id): Path<String>,
+) -> Result<()> {
+    debug!("{:<12} - dismiss_question_review, question_id: {}", "HANDLER", question_id);
+
+    gm.stats().dismiss_review(&question_id).await
+}
+
+/// 사용자별 기록 ("내 기록"과 같은 내용)
+pub async fn user_stats(
+    State(gm): State<GameManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<UserStats>> {
+    debug!("{:<12} - user_stats, user_id: {}", "HANDLER", user_id);
+
+    Ok(Json(gm.profiles().user_stats(&user_id).await?))
+}
@@ -1,15 +1,21 @@
+use std::collections::HashMap;
+
 use axum::{Json, Router};
 use axum::extract::State;
 use axum::routing::post;
-use kakao_rs::prelude::{BasicCard, SimpleImage, SimpleText, Template};
-use tracing::debug;
+use kakao_rs::prelude::{BasicCard, Button, Carousel};
+use tracing::{debug, warn};
 
-use crate::web::model::Command;
+use crate::web::model::{ChallengeCommand, Command, START_CATEGORY_CHOICES, START_CATEGORY_CONTEXT, START_CATEGORY_CONTEXT_TTL_SECS, START_OPTIONS_PARAM};
 
 use crate::{Error, game, Result};
+use crate::game::daily::DAILY_LEADERBOARD_SIZE;
 use crate::game::db::QuizType;
+use crate::game::model::{ImageLayout, Question};
+use crate::game::profile::{display_name, RANKING_SIZE};
 use crate::game::state::GameManager;
-use crate::skill::{Extra, Mention, TemplateWithExtra};
+use crate::skill::{escape_mentions, send_callback, CallbackAck, ContextControl, SkillResponse, TemplateWithExtra};
+use crate::web::response::{Priority, ResponseBuilder};
 use crate::web::model::BotRequest;
 use crate::web::model::ChatIdType::BotGroupKey;
 
@@ -21,10 +27,13 @@ pub fn routes(
         .with_state(gm)
 }
 
+// 콜백으로 보낼 때 먼저 보여주는 메시지
+const CALLBACK_WAIT_MESSAGE: &str = "⏳ 집계하고 있어요. 잠시만 기다려 주세요";
+
 pub async fn bot_request(
     State(gm): State<GameManager>,
     Json(payload): Json<BotRequest>,
-) -> Result<Json<TemplateWithExtra>> {
+) -> Result<Json<SkillResponse>> {
     debug!("{:<12} - bot_request", "HANDLER");
 
     let user_id = payload.user_request.user.id;
@@ -35,46 +44,134 @@ pub async fn bot_request(
 
     let utterance = payload.user_request.utterance;
     let command = Command::from_utterance(&utterance)
+        .or_else(|| Command::from_context(&payload.contexts, &utterance))
         .ok_or(Error::GameCommandParseFail(r#"🗒️ 명령어 목록
-- 시작 [카테고리]: 카테고리를 입력하지 않으면 전체 문제를 대상으로 출제됩니다.
-  (사용 가능 카테고리: 국기(추천), 상식, 고사성어)
+- 시작 [카테고리]: 카테고리를 입력하지 않으면 고를 수 있어요 ('전체'는 모든 문제).
+  (사용 가능 카테고리: 국기(추천), 상식, 고사성어, 초성, 수도, 나라, 국기찾기, 수학, 이미지 퀴즈 세트)
+- 시작 [카테고리] [쉬움|보통|어려움|적응형]: 난이도 선택 (상식, 고사성어, 넌센스, 수학만. 적응형은 빨리 맞히면 어려워짐)
+- 시작 [카테고리] [모드]: 클래식(기본, N문제), N점(먼저 N점), 서바이벌(틀리면 탈락), 블리츠(N분 동안)
+  ex) 시작 국기 10문제, 시작 상식 5점, 시작 수학 블리츠3분
+- 시작 [카테고리] 팀전: A팀/B팀으로 나눠 점수 대결
+- 시작 [카테고리] 대기실: 참가자를 모은 뒤 시작 ('참가'로 참여, 방장이 '출발' 또는 1분 뒤 자동 시작)
+- 시작 초성 [카테고리]: 해당 카테고리 정답의 초성 퀴즈
 - 중지
+- 일시정지 / 재개: 타이머를 멈추고 답을 받지 않음 (오래 멈춰 두면 자동 종료)
 - 정답 OOO
-- 랭킹(🚧)"#))?;
+- 패스: 정답을 공개하고 다른 문제로
+- 힌트: 정답의 초성과 글자 수 (부를 때마다 한 글자씩 더)
+- 팀 [A|B]: 팀전에서 팀 고르기 (안 고르면 자동 배정)
+- 도전 [카테고리] [N문제]: 다른 방과 같은 문제로 대결할 도전장 만들기
+- 도전 [코드]: 다른 방의 도전 받기
+- 오늘의 퀴즈: 모두에게 같은 오늘의 5문제 (하루 한 번, 답은 '오늘 OOO')
+- 오늘의 퀴즈 결과: 오늘의 퀴즈 순위
+- 닉네임 OOO: 순위표에 보여줄 이름 (한글, 영문, 숫자 2~10자)
+- 내 정보: 닉네임, 누적 점수, 순위, 가장 많이 맞힌 카테고리
+- 내 기록: 게임 수, 1등 횟수, 카테고리별 정답, 가장 빠른 정답, 최고 연속 정답
+- 랭킹: 누적 점수 순위"#))?;
+
+    // 오래 걸릴 수 있는 명령은 기다려 달라고 먼저 답하고, 결과는 콜백으로 보낸다
+    if let Some(callback_url) = payload.user_request.callback_url.filter(|_| command.is_slow()) {
+        tokio::spawn(async move {
+            let response = handle_command(&gm, command, user_id, chat_id)
+                .await
+                .unwrap_or_else(|e| TemplateWithExtra {
+                    template: e.template(),
+                    extra: None,
+                    context: None,
+                });
+            debug!("{:<12} - callback response: {:?}", "HANDLER", response);
+
+            if let Err(e) = send_callback(&callback_url, &response).await {
+                warn!("{:<12} - callback failed: {:?}", "HANDLER", e);
+            }
+        });
+        return Ok(Json(SkillResponse::Callback(CallbackAck::new(CALLBACK_WAIT_MESSAGE))));
+    }
+
+    let response = handle_command(&gm, command, user_id, chat_id).await?;
+    debug!("{:<12} - response: {:?}", "HANDLER", response);
 
-    let mut template = Template::new();
-    let mut extra: Option<Extra> = None;
+    Ok(Json(SkillResponse::Template(response)))
+}
+
+async fn handle_command(
+    gm: &GameManager,
+    command: Command,
+    user_id: String,
+    chat_id: String,
+) -> Result<TemplateWithExtra> {
+    let mut response = ResponseBuilder::new();
 
     match command {
-        Command::Start(category) => {
-            let is_flag_quiz = category.as_deref() == Some("국기");
-            let game = gm.start_game(chat_id, category, is_flag_quiz).await?;
-
-            // todo: extract
-            match &game.current_quiz {
-                QuizType::Simple(quiz) => {
-                    template.add_output(SimpleText::new(quiz.info_before(game.current_round)).build());
-                }
-                QuizType::Flag(quiz) => {
-                    // BasicCard -> 이미지 비율이 제한적이라 안쓰는걸루
-                    // response.add_output(
-                    //     BasicCard::new()
-                    //         .set_title(quiz.title(game.current_round))
-                    //         .set_description("이 국기는 어느 나라의 국기일까요?")
-                    //         .set_thumbnail(quiz.image_url())
-                    //     .build()
-                    // )
-
-                    template.add_output(SimpleImage::new(quiz.image_url(), quiz.country_code_alpha_2.clone()).build());
-                    template.add_output(SimpleText::new(quiz.info_before(game.current_round)).build());
-                    // 임시로 답도 알려준다.
-                    // template.add_output(SimpleText::new(format!("빈스 치트 - {}", quiz.answer.clone())).build());
-                }
+        Command::AskCategory(options) => {
+            response.text("🗂️ 어떤 카테고리로 할까요?");
+            for category in START_CATEGORY_CHOICES {
+                response.quick_reply(category, category);
+            }
+            // 다음 발화 한 번만. 같이 입력한 옵션은 그대로 이어간다
+            response.context(ContextControl::single(
+                START_CATEGORY_CONTEXT,
+                1,
+                START_CATEGORY_CONTEXT_TTL_SECS,
+                HashMap::from([(START_OPTIONS_PARAM.to_string(), options)]),
+            ));
+        }
+        Command::Start(options) if options.lobby => {
+            gm.open_lobby(chat_id, &user_id, options).await?;
+
+            let host = response.mention(&user_id);
+            response.text(format!(
+                "🚪 대기실이 열렸어요! '참가'로 참여하세요\n{} 님이 '출발'하면 시작합니다 (1분 뒤 자동 시작)",
+                host,
+            ));
+        }
+        Command::JoinLobby => {
+            let count = gm.join_lobby(&chat_id, &user_id).await?;
+
+            let user = response.mention(&user_id);
+            response.text(format!("🙋 {} 참가! (현재 {}명)", user, count));
+        }
+        Command::StartLobby => {
+            let game = gm.start_lobby(&chat_id, &user_id).await?;
+
+            response.text(format!(
+                "🟢 {} 게임을 시작합니다! (참가 {}명)",
+                game.mode.name(), game.roster.as_ref().map_or(0, |roster| roster.len()),
+            ));
+            add_quiz_outputs(&mut response, &game.current_quiz, &game.round_header());
+        }
+        Command::Start(options) => {
+            let game = gm.start_game(chat_id, options).await?;
+
+            let mut start_text = format!("🟢 {} 게임을 시작합니다!", game.mode.name());
+            if game.teams.is_some() {
+                start_text.push_str("\n🚩 팀전! '팀 A' 또는 '팀 B'로 팀을 고르세요 (안 고르면 자동 배정)");
             }
+            response.text(start_text);
+            add_quiz_outputs(&mut response, &game.current_quiz, &game.round_header());
         }
         Command::Stop => {
-            gm.stop_game(chat_id).await?;
-            template.add_output(SimpleText::new("🔴 퀴즈게임이 종료되었습니다.").build());
+            if gm.close_lobby(&chat_id).await {
+                response.text("🚪 대기실을 닫았어요");
+            } else {
+                let summary = gm.stop_game(chat_id).await?;
+                let mut text = "🔴 퀴즈게임이 종료되었습니다.".to_string();
+                if let Some(standings) = summary.standings(response.mentions()) {
+                    text.push_str(format!("\n{}", standings).as_str());
+                }
+                response.text(text);
+            }
+        }
+        Command::Pause => {
+            gm.pause_game(&chat_id).await?;
+
+            response.text("⏸️ 일시정지! '재개'하면 이어서 진행합니다");
+        }
+        Command::Resume => {
+            let game = gm.resume_game(&chat_id).await?;
+
+            response.text("▶️ 게임을 재개합니다!");
+            add_quiz_outputs(&mut response, &game.current_quiz, &game.round_header());
         }
         Command::Answer(answer) => {
             let result = gm.try_answer_inmemory(&user_id, &chat_id, &answer).await?;
@@ -82,72 +179,406 @@ pub async fn bot_request(
                 game::state::AnswerResult::Correct {
                     user_id,
                     score,
+                    game_score,
+                    team,
                     current_quiz,
                     next_quiz,
-                    current_round
+                    round_header,
+                    difficulty_changed,
+                    summary,
+                    ..
                 } => {
-                    // TODO: hash -> nickname?
-                    // let mut result_text = format!("👏 {:.6} 정답! (누적 점수: {})", user_id, score);
-                    let mut result_text = format!(r#"👏 {{{{#mentions.user}}}} 정답! (누적 점수: {})"#, score);
-                    extra = Some(Extra {
-                        mentions: vec![("user".to_string(), Mention {
-                            mention_type: "botUserKey".to_string(),
-                            id: user_id,
-                        })].into_iter().collect(),
-                    });
-
-                    match &current_quiz {
+                    let user = response.mention(&user_id);
+                    let mut result_text = match score {
+                        Some(score) => format!("👏 {} 정답! (이번 게임: {}점, 누적 점수: {})", user, game_score, score),
+                        None => format!("👏 {} 정답! (이번 게임: {}점)", user, game_score),
+                    };
+                    if let Some(team) = team {
+                        result_text.push_str(format!("\n🚩 {} +1", team.name()).as_str());
+                    }
+
+                    match current_quiz.as_ref() {
                         QuizType::Simple(quiz) => {
                             if let Some(comment) = quiz.comment.clone() {
-                                result_text.push_str(format!("\n{}", comment).as_str());
+                                result_text.push_str(format!("\n{}", escape_mentions(&comment)).as_str());
                             }
                         }
-                        QuizType::Flag(_) => {
+                        _ => {
                             // no-op
                         }
                     }
 
-                    template.add_output(SimpleText::new(result_text).build());
+                    if let Some(difficulty) = difficulty_changed {
+                        result_text.push_str(format!("\n📶 난이도 변경: {}", difficulty.name()).as_str());
+                    }
 
-                    if current_round > game::state::MAX_ROUNDS {
-                        template.add_output(SimpleText::new("✅ 다 풀었습니다 :)").build());
-                        gm.stop_game(chat_id).await?;
-                    } else {
-                        // TODO: extract
-                        match &next_quiz {
-                            QuizType::Simple(quiz) => {
-                                template.add_output(SimpleText::new(quiz.info_before(current_round)).build());
-                            }
-                            QuizType::Flag(quiz) => {
-                                template.add_output(SimpleImage::new(quiz.image_url(), quiz.country_code_alpha_2.clone()).build());
-                                template.add_output(SimpleText::new(quiz.info_before(current_round)).build());
-                                // 임시로 답도 알려준다.
-                                // response.add_output(SimpleText::new(format!("빈스 치트 - {}", quiz.answer.clone())).build());
-                                // outputs는 3개까지....
-                            }
-                        }
+                    response.text(result_text);
+
+                    if let Some(summary) = summary {
+                        let message = summary.message(response.mentions());
+                        response.text(message);
+                    } else if let Some(next_quiz) = next_quiz {
+                        add_quiz_outputs(&mut response, &next_quiz, &round_header);
+                    }
+                }
+                game::state::AnswerResult::Wrong { eliminated, summary } => {
+                    // 서바이벌이 아니면 no-op
+                    if eliminated {
+                        let user = response.mention(&user_id);
+                        response.text(format!("💀 {} 탈락!", user));
                     }
+                    if let Some(summary) = summary {
+                        let message = summary.message(response.mentions());
+                        response.text(message);
+                    }
+                }
+                game::state::AnswerResult::AlreadyEliminated => {
+                    response.text("💀 탈락한 사람은 이번 게임에서 답할 수 없어요");
+                }
+                game::state::AnswerResult::Paused => {
+                    response.text("⏸️ 일시정지 중이에요. '재개'로 다시 시작하세요");
+                }
+                game::state::AnswerResult::NotJoined => {
+                    response.text("🚪 대기실에서 참가한 사람만 점수를 얻을 수 있어요");
                 }
-                game::state::AnswerResult::Wrong => {
-                    // no-op
-                    // response.add_output(SimpleText::new("[DEBUG] 땡").build());
+                game::state::AnswerResult::Late { winner: Some(winner) } => {
+                    let winner = response.mention(&winner);
+                    response.text(format!("😢 아깝게 늦었어요! {} 님이 먼저 맞혔어요", winner));
+                }
+                game::state::AnswerResult::Late { winner: None } => {
+                    response.text("😢 아깝게 늦었어요! 이미 다음 문제로 넘어갔어요");
+                }
+                game::state::AnswerResult::TimeOver(summary) => {
+                    let message = summary.message(response.mentions());
+                    response.text(message);
                 }
             }
         }
+        Command::Skip => {
+            let result = gm.skip_question(&chat_id).await?;
+            response.text(format!("⏭️ 패스! 정답은 '{}'였어요", escape_mentions(&result.skipped_quiz.as_question().reveal_answer())));
+            if let Some(summary) = result.summary {
+                let message = summary.message(response.mentions());
+                response.text(message);
+            } else if let Some(next_quiz) = result.next_quiz {
+                add_quiz_outputs(&mut response, &next_quiz, &result.round_header);
+            }
+        }
+        Command::Hint => {
+            let hint = gm.hint(&chat_id).await?;
+            response.text(format!("💡 힌트: {}", escape_mentions(&hint)));
+        }
+        Command::JoinTeam(team) => {
+            let result = gm.join_team(&user_id, &chat_id, team).await?;
+            let counts = result.member_counts.iter()
+                .map(|(team, count)| format!("{} {}명", team.name(), count))
+                .collect::<Vec<_>>()
+                .join(" / ");
+            let user = response.mention(&user_id);
+            response.text(format!("🚩 {} 님은 {}! ({})", user, result.team.name(), counts));
+        }
+        Command::Challenge(ChallengeCommand::Create(options)) => {
+            let (code, game) = gm.create_challenge(chat_id, options).await?;
+
+            response.text(format!(
+                "⚔️ 도전장을 만들었어요! 코드: {}\n다른 방에서 '도전 {}'을 입력하면 같은 문제로 대결합니다 ({}, 30분 안에)",
+                code, code, game.mode.name(),
+            ));
+            add_quiz_outputs(&mut response, &game.current_quiz, &game.round_header());
+        }
+        Command::Challenge(ChallengeCommand::Accept(code)) => {
+            let game = gm.accept_challenge(chat_id, &code).await?;
+
+            response.text(format!("⚔️ 도전 {} 수락! 같은 문제로 대결합니다 ({})", code, game.mode.name()));
+            add_quiz_outputs(&mut response, &game.current_quiz, &game.round_header());
+        }
+        Command::DailyStart => {
+            let question = gm.daily().start(&user_id).await?;
+
+            add_quiz_outputs(&mut response, &question.quiz, &question.round_header);
+        }
+        Command::DailyAnswer(answer) => {
+            let result = gm.daily().answer(&user_id, &answer).await?;
+
+            let mut text = if result.correct {
+                "⭕ 정답!".to_string()
+            } else {
+                format!("❌ 땡! 정답은 '{}'", escape_mentions(&result.revealed_answer))
+            };
+            if let Some(record) = &result.finished {
+                text.push_str(format!(
+                    "\n🎉 오늘의 퀴즈 완료! {}문제 중 {}\n'오늘의 퀴즈 결과'로 순위를 확인하세요",
+                    result.total, record.describe(),
+                ).as_str());
+            }
+            response.text(text);
+
+            if let Some(next) = result.next {
+                add_quiz_outputs(&mut response, &next.quiz, &next.round_header);
+            }
+        }
+        Command::DailyResult => {
+            let leaderboard = gm.daily().leaderboard().await?;
+
+            let mut text = format!("📅 오늘의 퀴즈 순위 ({})", leaderboard.date);
+            if leaderboard.records.is_empty() {
+                text.push_str("\n아직 다 푼 사람이 없어요");
+            }
+            let top_user_ids: Vec<String> = leaderboard.records.iter()
+                .take(DAILY_LEADERBOARD_SIZE)
+                .map(|record| record.user_id.clone())
+                .collect();
+            let nicknames = gm.profiles().nicknames(&top_user_ids).await?;
+            for (i, record) in leaderboard.records.iter().take(DAILY_LEADERBOARD_SIZE).enumerate() {
+                let name = escape_mentions(&display_name(&record.user_id, nicknames.get(&record.user_id).map(String::as_str)));
+                let me = if record.user_id == user_id { " (나)" } else { "" };
+                text.push_str(format!("\n{}. {}{} - {}", i + 1, name, me, record.describe()).as_str());
+            }
+            if let Some(rank) = leaderboard.rank_of(&user_id).filter(|rank| *rank > DAILY_LEADERBOARD_SIZE) {
+                text.push_str(format!("\n...\n{}. 나 - {}", rank, leaderboard.records[rank - 1].describe()).as_str());
+            }
+            response.text(text);
+        }
         Command::Ranking => {
-            // let (user_rank, chat_rank) = gm.get_ranking(&user_id, &chat_id).await?;
-            // response.add_output(SimpleText::new(format!("당신의 순위: {}등\n이 방의 순위: {}등", user_rank, chat_rank)).build());
+            let top_scores = gm.profiles().top_scores(RANKING_SIZE).await?;
+            let top_user_ids: Vec<String> = top_scores.iter().map(|(user_id, _)| user_id.clone()).collect();
+            let nicknames = gm.profiles().nicknames(&top_user_ids).await?;
+
+            let mut text = "🏆 누적 점수 순위".to_string();
+            if top_scores.is_empty() {
+                text.push_str("\n아직 맞힌 사람이 없어요");
+            }
+            for (i, (ranked_user_id, score)) in top_scores.iter().enumerate() {
+                let name = escape_mentions(&display_name(ranked_user_id, nicknames.get(ranked_user_id).map(String::as_str)));
+                let me = if *ranked_user_id == user_id { " (나)" } else { "" };
+                text.push_str(format!("\n{}. {}{} - {}점", i + 1, name, me, score).as_str());
+            }
+            response.text(text);
+        }
+        Command::SetNickname(nickname) => {
+            let nickname = gm.profiles().set_nickname(&user_id, &nickname).await?;
+
+            response.text(format!("🏷️ 이제 순위표에 '{}'(으)로 보여요", nickname));
+        }
+        Command::Profile => {
+            let profile = gm.profiles().profile(&user_id).await?;
+            let (rank, group_rank) = gm.get_ranking(&user_id, &chat_id).await?;
+
+            let mut text = match &profile.nickname {
+                Some(nickname) => format!("🙂 {}", escape_mentions(nickname)),
+                None => "🙂 닉네임 없음 ('닉네임 OOO'로 정할 수 있어요)".to_string(),
+            };
+            text.push_str(format!("\n- 누적 점수: {}점", profile.score).as_str());
+            if let Some(rank) = rank {
+                text.push_str(format!("\n- 순위: {}등", rank).as_str());
+            }
+            if let Some(group_rank) = group_rank {
+                text.push_str(format!("\n- 이 방 순위: {}등 (방별 누적 점수)", group_rank).as_str());
+            }
+            if let Some((category, correct)) = &profile.favorite_category {
+                text.push_str(format!("\n- 가장 많이 맞힌 카테고리: {} ({}문제)", category, correct).as_str());
+            }
+            response.text(text);
+        }
+        Command::Records => {
+            let stats = gm.profiles().user_stats(&user_id).await?;
+
+            let mut text = format!(
+                "📒 {} 님의 기록\n- 게임: {}판 (1등 {}번)\n- 정답: {}문제",
+                escape_mentions(&display_name(&user_id, stats.nickname.as_deref())),
+                stats.games_played, stats.first_places, stats.total_correct(),
+            );
+            if let Some(fastest_ms) = stats.fastest_answer_ms {
+                text.push_str(format!("\n- 가장 빠른 정답: {:.1}초", fastest_ms as f64 / 1000.0).as_str());
+            }
+            if stats.longest_streak > 0 {
+                text.push_str(format!("\n- 최고 연속 정답: {}문제", stats.longest_streak).as_str());
+            }
+            let categories = stats.categories_by_correct();
+            if !categories.is_empty() {
+                let categories = categories.iter()
+                    .map(|(category, correct)| format!("{} {}", category, correct))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                text.push_str(format!("\n- 카테고리별: {}", categories).as_str());
+            }
+            response.text(text);
+        }
+    }
+
+    Ok(response.build())
+}
+
+/// 문제 출제용 output. 앞에 안내 텍스트가 하나 올 수 있도록 최대 2개만 쓴다.
+/// 문제 글은 데이터에서 온 것이라 멘션으로 바뀌지 않게 escape 한다.
+/// 문제는 넘쳐도 빠지지 않게 `High`. 수도 문제의 국기처럼 곁들이는 이미지는 `Low`
+fn add_quiz_outputs(response: &mut ResponseBuilder, quiz: &QuizType, round_header: &str) {
+    match quiz {
+        QuizType::Simple(quiz) => {
+            response.text(escape_mentions(&quiz.info_before(round_header))).priority(Priority::High);
+        }
+        QuizType::Flag(quiz) => {
+            // BasicCard -> 이미지 비율이 제한적이라 안쓰는걸루
+            // response.add_output(
+            //     BasicCard::new()
+            //         .set_title(quiz.title(game.current_round))
+            //         .set_description("이 국기는 어느 나라의 국기일까요?")
+            //         .set_thumbnail(quiz.image_url())
+            //     .build()
+            // )
 
-            template.add_output(SimpleText::new("🚧 공사중").build());
+            response.image(quiz.image_url(), quiz.country_code_alpha_2.clone()).priority(Priority::High);
+            response.text(escape_mentions(&quiz.info_before(round_header))).priority(Priority::High);
+            // 임시로 답도 알려준다.
+            // response.text(format!("빈스 치트 - {}", quiz.answer.clone()));
+        }
+        QuizType::Chosung(_) | QuizType::Math(_) => {
+            response.text(escape_mentions(&quiz.as_question().info_before(round_header))).priority(Priority::High);
+        }
+        QuizType::Capital(quiz) => {
+            // 나라 -> 수도일 때만 국기를 같이 보여준다 (수도 -> 나라는 국기가 곧 정답)
+            if !quiz.reverse {
+                response.image(quiz.image_url(), quiz.country_code_alpha_2.clone()).priority(Priority::Low);
+            }
+            response.text(escape_mentions(&quiz.info_before(round_header))).priority(Priority::High);
+        }
+        QuizType::FlagChoice(quiz) => {
+            let mut carousel = Carousel::new().set_type(BasicCard::id());
+            for (i, image_url) in quiz.choice_image_urls().into_iter().enumerate() {
+                let number = i + 1;
+                carousel.add_card(
+                    BasicCard::new()
+                        .set_title(format!("{}번", number))
+                        .set_thumbnail(image_url)
+                        .add_button(Button::text("선택").set_msg(format!("정답 {}", number)))
+                        .build_card()
+                );
+            }
+            response.output(carousel.build()).priority(Priority::High);
+            response.text(escape_mentions(&quiz.info_before(round_header))).priority(Priority::High);
+        }
+        QuizType::Image(quiz) => {
+            // 로딩할 때 검증해서 url이 없을 수는 없다
+            let image_url = quiz.image_url().unwrap_or_default();
+            match quiz.layout() {
+                ImageLayout::SquareCard | ImageLayout::WideCard => {
+                    response.output(
+                        BasicCard::new()
+                            .set_title(escape_mentions(&quiz.title(round_header)))
+                            .set_description(escape_mentions(quiz.question()))
+                            .set_thumbnail(image_url)
+                            .set_fixed_ratio(quiz.layout() == ImageLayout::SquareCard)
+                            .build()
+                    ).priority(Priority::High);
+                }
+                ImageLayout::SimpleImage => {
+                    response.image(image_url, quiz.alt.clone()).priority(Priority::High);
+                    response.text(escape_mentions(&quiz.info_before(round_header))).priority(Priority::High);
+                }
+            }
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
 
-    let template_with_extra = TemplateWithExtra {
-        template,
-        extra,
-    };
+    use serde_json::{json, Value};
 
-    debug!("{:<12} - response: {:?}", "HANDLER", template_with_extra);
+    use super::*;
+    use crate::game::model::Quiz;
+    use crate::game::state::tests::test_manager;
+    use crate::game::store::InMemoryGameStore;
 
-    Ok(Json(template_with_extra))
+    // 콜백으로 받은 body
+    type Received = Arc<Mutex<Vec<Value>>>;
+
+    /// `routes`와 카카오 콜백 서버 대신 받는 `/callback`을 한 서버에 띄운다
+    async fn serve() -> (String, Received) {
+        let received: Received = Arc::default();
+        let callback = Router::new()
+            .route("/callback", post(|State(received): State<Received>, Json(body): Json<Value>| async move {
+                received.lock().unwrap().push(body);
+                Json(json!({"taskId": "task", "status": "SUCCESS"}))
+            }))
+            .with_state(received.clone());
+        let app = routes(test_manager(Arc::new(InMemoryGameStore::default()), false)).merge(callback);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (base_url, received)
+    }
+
+    async fn send(base_url: &str, utterance: &str) -> Value {
+        let request = json!({
+            "userRequest": {
+                "user": {"id": "user"},
+                "chat": {"id": "room", "type": "botGroupKey"},
+                "utterance": utterance,
+                "callbackUrl": format!("{}/callback", base_url),
+            },
+        });
+        reqwest::Client::new().post(format!("{}/", base_url))
+            .json(&request)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn slow_command_answers_through_callback() {
+        let (base_url, received) = serve().await;
+
+        let response = send(&base_url, "랭킹").await;
+        assert_eq!(response, json!({
+            "version": "2.0",
+            "useCallback": true,
+            "data": {"text": CALLBACK_WAIT_MESSAGE},
+        }));
+
+        crate::game::state::tests::wait_until(|| async { !received.lock().unwrap().is_empty() }).await;
+        let body = received.lock().unwrap()[0].clone();
+        serde_json::from_value::<TemplateWithExtra>(body.clone()).unwrap();
+        assert_eq!(body["version"], "2.0");
+        assert_eq!(body["template"]["outputs"], json!([
+            {"simpleText": {"text": "🏆 누적 점수 순위\n아직 맞힌 사람이 없어요"}},
+        ]));
+    }
+
+    #[test]
+    fn quiz_text_is_escaped() {
+        let quiz: Quiz = serde_json::from_value(json!({
+            "category": "상식",
+            "question": "{{#mentions.user1}}은 누구일까요?",
+            "answer": "정답",
+        })).unwrap();
+        let mut response = ResponseBuilder::new();
+        let user = response.mention("user");
+        response.text(format!("👏 {} 정답!", user));
+        add_quiz_outputs(&mut response, &QuizType::Simple(quiz), "[2/3]");
+
+        let body = serde_json::to_value(response.build()).unwrap();
+        let question = body["template"]["outputs"][1]["simpleText"]["text"].as_str().unwrap();
+        assert_eq!(question, escape_mentions("[2/3] (상식)\n{{#mentions.user1}}은 누구일까요?"));
+        assert!(!question.contains(crate::skill::MENTION_PLACEHOLDER_PREFIX));
+    }
+
+    #[tokio::test]
+    async fn fast_command_answers_directly() {
+        let (base_url, received) = serve().await;
+
+        let response = send(&base_url, "중지").await;
+        assert!(response.get("useCallback").is_none());
+        assert_eq!(response["template"]["outputs"][0]["simpleText"]["text"], "게임중이 아니에요");
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(received.lock().unwrap().is_empty());
+    }
 }
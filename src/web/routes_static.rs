@@ -0,0 +1,21 @@
+use axum::http::header::CACHE_CONTROL;
+use axum::http::HeaderValue;
+use axum::Router;
+use tower_http::services::ServeDir;
+use tower_http::set_header::SetResponseHeaderLayer;
+
+use crate::config::STATIC_ROUTE_PATH;
+
+// 국기/퀴즈 이미지는 거의 안 바뀌니 길게
+const STATIC_CACHE_CONTROL: &str = "public, max-age=86400";
+
+/// 카카오 이미지 서버가 가져가는 용도라 인증 헤더 없이 접근 가능해야 한다.
+/// (mw_header_checker 레이어 바깥에 merge 할 것)
+pub fn routes(static_files_dir: &str) -> Router {
+    Router::new()
+        .nest_service(STATIC_ROUTE_PATH, ServeDir::new(static_files_dir))
+        .layer(SetResponseHeaderLayer::if_not_present(
+            CACHE_CONTROL,
+            HeaderValue::from_static(STATIC_CACHE_CONTROL),
+        ))
+}